@@ -1,3 +0,0 @@
-pub mod parser;
-pub mod tasks;
-pub mod utility;
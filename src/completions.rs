@@ -0,0 +1,130 @@
+use anyhow::{bail, Result};
+use clap_complete::Shell;
+
+/// subcommands whose first argument is the name of a task in the ledger.
+const TASK_COMMANDS: [&str; 3] = ["end", "fix", "continue"];
+
+/// wraps the generated bash completion, completing task names from `timr complete-tasks`.
+const BASH: &str = r#"
+_timr() {
+    if [[ ${COMP_CWORD} -eq 2 && " __TASK_COMMANDS__ " == *" ${COMP_WORDS[1]} "* ]]; then
+        local cur="${COMP_WORDS[COMP_CWORD]//\\ / }" name
+        COMPREPLY=()
+        while IFS= read -r name; do
+            [[ "$name" == "$cur"* ]] && COMPREPLY+=("$(printf '%q' "$name")")
+        done < <(timr complete-tasks 2>/dev/null)
+        return 0
+    fi
+    _timr_generated "$@"
+}
+"#;
+
+const ZSH: &str = r#"
+_timr() {
+    if (( CURRENT == 3 )) && [[ " __TASK_COMMANDS__ " == *" ${words[2]} "* ]]; then
+        local -a names
+        names=("${(@f)$(timr complete-tasks 2>/dev/null)}")
+        compadd -a names
+        return
+    fi
+    _timr_generated "$@"
+}
+"#;
+
+const FISH: &str = r#"
+complete -c timr -n "__fish_seen_subcommand_from __TASK_COMMANDS__; and test (count (commandline -opc)) -eq 2" -f -a "(timr complete-tasks 2>/dev/null)"
+"#;
+
+const POWERSHELL: &str = r#"
+Register-ArgumentCompleter -Native -CommandName 'timr' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $elements = $commandAst.CommandElements
+    $position = $elements.Count
+    if ($wordToComplete) { $position -= 1 }
+    if ($position -eq 2 -and @('__TASK_COMMANDS__'.Split(' ')) -contains $elements[1].Value) {
+        $word = $wordToComplete.Trim("'", '"')
+        timr complete-tasks 2>$null | Where-Object { $_ -like "$word*" } | ForEach-Object {
+            [CompletionResult]::new("'$($_ -replace "'", "''")'", $_, [CompletionResultType]::ParameterValue, $_)
+        }
+        return
+    }
+    & $global:__timrGenerated $wordToComplete $commandAst $cursorPosition
+}
+"#;
+
+/// replaces the one `from` in `script` with `to`.
+fn rename(script: String, from: &str, to: &str) -> Result<String> {
+    if script.matches(from).count() != 1 {
+        bail!(
+            "could not find {} in the generated completions",
+            from.trim()
+        );
+    }
+    Ok(script.replacen(from, to, 1))
+}
+
+/// the completion script for `shell`. the script clap generates is extended to complete
+/// task names for `end`, `fix`, and `continue`, read from the ledger when tab is pressed.
+pub fn script(shell: Shell, cmd: &mut clap::Command) -> Result<String> {
+    let mut buf: Vec<u8> = Vec::new();
+    clap_complete::generate(shell, cmd, "timr", &mut buf);
+    let generated = String::from_utf8(buf)?;
+
+    let (generated, extra) = match shell {
+        Shell::Bash => (rename(generated, "_timr() {", "_timr_generated() {")?, BASH),
+        Shell::Zsh => (rename(generated, "_timr() {", "_timr_generated() {")?, ZSH),
+        Shell::Fish => (generated, FISH),
+        Shell::PowerShell => (
+            rename(
+                generated,
+                "Register-ArgumentCompleter -Native -CommandName 'timr' -ScriptBlock {",
+                "$global:__timrGenerated = {",
+            )?,
+            POWERSHELL,
+        ),
+        _ => return Ok(generated),
+    };
+    let extra = extra.replace("__TASK_COMMANDS__", &TASK_COMMANDS.join(" "));
+
+    // zsh runs the script as the `_timr` function, which has to be defined before its last lines.
+    Ok(match shell {
+        Shell::Zsh => {
+            let (body, tail) = match generated.rsplit_once("\nif [ \"$funcstack[1]\" = \"_timr\" ]")
+            {
+                Some(split) => split,
+                None => bail!("could not find the end of the generated zsh completions"),
+            };
+            format!(
+                "{}{}\nif [ \"$funcstack[1]\" = \"_timr\" ]{}",
+                body, extra, tail
+            )
+        }
+        _ => generated + &extra,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_script() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::PowerShell,
+            Shell::Elvish,
+        ] {
+            let s = script(shell, &mut crate::parser::Cli::command()).unwrap();
+            assert!(s.contains("continue"), "{}", shell);
+            if shell != Shell::Elvish {
+                assert!(s.contains("timr complete-tasks"), "{}", shell);
+            }
+        }
+        let zsh = script(Shell::Zsh, &mut crate::parser::Cli::command()).unwrap();
+        assert!(zsh.find("\n_timr() {").unwrap() < zsh.rfind("compdef _timr timr").unwrap());
+    }
+}
@@ -0,0 +1,8 @@
+//! timr: a handy timecard calculator plus a way to keep track of time spent on tasks.
+//!
+//! The [`Timr`] service is what the command line app is built on, and can be
+//! embedded in other front ends the same way.
+pub mod util;
+
+pub use util::service::{ListFilter, LogTimes, Query, Report, Sort, Timr};
+pub use util::tasks::Task;
@@ -1,5 +1,12 @@
-mod util;
+mod completions;
+mod parser;
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "util/testing.rs"]
+mod testing;
+mod ui;
+mod watch;
 
 fn main() -> anyhow::Result<()> {
-    util::parser::do_parse()
-}
\ No newline at end of file
+    parser::do_parse()
+}
@@ -0,0 +1,1267 @@
+use anyhow::Ok;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use timr::util::archive::{self, Period};
+use timr::util::billing::InvoiceFormat;
+use timr::util::calc;
+use timr::util::config::{Config, CONFIG_FILE};
+use timr::util::dates::parse_range;
+use timr::util::doctor;
+use timr::util::duration::{Duration, DurationStyle};
+use timr::util::encryption::{self, Secret};
+use timr::util::hook::{self, Shell};
+use timr::util::pomodoro::{Phase, Pomodoro};
+use timr::util::rounding::RoundingScope;
+use timr::util::schema;
+use timr::util::server::Server;
+use timr::util::sync;
+use timr::util::utility::OUTPUT_FILE;
+use timr::{ListFilter, LogTimes, Query, Sort, Task, Timr};
+
+/// a command line copy of a library enum, so that the library does not depend on clap.
+/// the variants' doc comments are their help.
+macro_rules! arg_enum {
+    ($arg:ident => $lib:ty { $($(#[doc = $doc:literal])* $variant:ident,)* }) => {
+        #[derive(Clone, Copy, ValueEnum)]
+        enum $arg {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        impl From<$arg> for $lib {
+            fn from(arg: $arg) -> Self {
+                match arg {
+                    $($arg::$variant => <$lib>::$variant,)*
+                }
+            }
+        }
+    };
+}
+
+arg_enum!(StyleArg => DurationStyle {
+    /// hours and minutes, e.g. "2:30".
+    Hmm,
+    /// decimal hours, e.g. "2.50".
+    Decimal,
+    /// e.g. "2 hours 30 minutes".
+    Verbose,
+    /// ISO 8601, e.g. "PT2H30M".
+    Iso,
+});
+
+arg_enum!(SortArg => Sort {
+    /// oldest first.
+    Date,
+    Name,
+    /// longest first.
+    Duration,
+});
+
+arg_enum!(PeriodArg => Period {
+    /// one file per month, e.g. `timr.2023-10.json`.
+    Month,
+    /// one file per year, e.g. `timr.2023.json`.
+    Year,
+});
+
+arg_enum!(InvoiceFormatArg => InvoiceFormat {
+    Text,
+    Markdown,
+    Html,
+});
+
+arg_enum!(ShellArg => Shell {
+    Bash,
+    Zsh,
+    Fish,
+});
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    /// Turn debugging information on
+    #[arg(short, long)]
+    pub debug: bool,
+
+    /// how to show lengths of time, instead of `duration_style` in the config
+    #[arg(long, global = true, value_enum)]
+    style: Option<StyleArg>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+/// which tasks to look at, shared by `list`, `report`, and `export`.
+/// dates can be natural ("monday", "last month") or YYYY-MM-DD.
+#[derive(Args)]
+struct QueryArgs {
+    /// tasks from this week, or from ISO week N of this year
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        conflicts_with_all = ["last_week", "today", "days", "on", "from", "to"]
+    )]
+    week: Option<Option<u32>>,
+
+    /// tasks from last week
+    #[arg(short, long, conflicts_with_all = ["today", "days", "on", "from", "to"])]
+    last_week: bool,
+
+    /// today's tasks
+    #[arg(short, long, conflicts_with_all = ["days", "on", "from", "to"])]
+    today: bool,
+
+    /// tasks from the last number of days
+    #[arg(short, long, conflicts_with_all = ["on", "from", "to"])]
+    days: Option<i32>,
+
+    /// tasks on a day, or in a span like "last month"
+    #[arg(long, conflicts_with_all = ["from", "to"])]
+    on: Option<String>,
+
+    /// tasks from this day on
+    #[arg(long)]
+    from: Option<String>,
+
+    /// tasks up to this day
+    #[arg(long)]
+    to: Option<String>,
+
+    /// only tasks whose name contains this
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// only tasks with this tag
+    #[arg(long)]
+    tag: Option<String>,
+
+    /// order the tasks
+    #[arg(short, long, value_enum)]
+    sort: Option<SortArg>,
+
+    /// show at most this many tasks
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+impl QueryArgs {
+    /// builds the query, or `None` if no range was given.
+    fn to_query(&self, timr: &Timr) -> anyhow::Result<Option<Query>> {
+        let today = timr.today();
+        let range = |s: &str| parse_range(s, today, timr.config().week_start);
+        let filter = match (self.week, self.last_week, self.today, self.days, &self.on) {
+            (Some(Some(n)), ..) => Some(ListFilter::IsoWeek(n)),
+            (Some(None), ..) => Some(ListFilter::Week),
+            (_, true, ..) => Some(ListFilter::LastWeek),
+            (_, _, true, ..) => Some(ListFilter::Today),
+            (_, _, _, Some(days), _) => Some(ListFilter::Days(days)),
+            (.., Some(on)) => {
+                let (from, to) = range(on)?;
+                Some(ListFilter::Between(Some(from), Some(to)))
+            }
+            _ => None,
+        };
+        let filter = match (filter, &self.from, &self.to) {
+            (Some(f), ..) => Some(f),
+            (None, None, None) => None,
+            (None, from, to) => Some(ListFilter::Between(
+                from.as_deref().map(range).transpose()?.map(|r| r.0),
+                to.as_deref().map(range).transpose()?.map(|r| r.1),
+            )),
+        };
+        Ok(filter.map(|filter| Query {
+            filter,
+            name: self.name.clone(),
+            tag: self.tag.clone(),
+            sort: self.sort.map(Sort::from),
+            limit: self.limit,
+        }))
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Start a task.
+    /// if no time is given, start time wil be current time.
+    // #[cmd(short, long)]
+    Start {
+        /// name of task "working on code-review #175" or kickoff-meeting.
+        /// with git enabled in the config, defaults to the current branch
+        task: Option<String>,
+        /// time started (HH:MM format)
+        time: Option<String>,
+        /// project the task is billed to
+        #[arg(short, long)]
+        project: Option<String>,
+        /// tag the task, can be given more than once
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+    },
+    /// log a finished task, e.g. `log "code review" --date yesterday --from 0900 --to 1130`.
+    /// give two of --from, --to, and --duration, or only --duration to end the task now.
+    /// a template from the config can be logged by name, and lasts the template's length
+    Log {
+        /// name of task, or of a template
+        task: String,
+        /// day the task started on. defaults to today
+        #[arg(long)]
+        date: Option<String>,
+        /// time started (HHMM)
+        #[arg(short, long)]
+        from: Option<String>,
+        /// time ended (HHMM). a time before the start is on the next day
+        #[arg(long)]
+        to: Option<String>,
+        /// how long the task took, e.g. 2h30m, 2.5h, 45m, or 2:30
+        #[arg(short, long)]
+        duration: Option<String>,
+        /// project the task is billed to
+        #[arg(short, long)]
+        project: Option<String>,
+        /// tag the task, can be given more than once
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// log the task even if it overlaps another one
+        #[arg(long)]
+        allow_overlap: bool,
+    },
+
+    /// log the recurring tasks in the config for a span of days,
+    /// skipping holidays and days that already have them
+    Fill {
+        /// fill this week
+        #[arg(short, long, conflicts_with_all = ["from", "to"])]
+        week: bool,
+        /// first day to fill, e.g. 2023-10-02 or "last monday"
+        #[arg(long, required_unless_present = "week")]
+        from: Option<String>,
+        /// last day to fill. defaults to today
+        #[arg(long)]
+        to: Option<String>,
+    },
+
+    /// start the most recent task by that name again, with the same tags and project.
+    /// with no name, continues the last task that was ended
+    Continue {
+        /// name of task
+        task: Option<String>,
+    },
+    /// End a task. if no time is given, end time will be current time.
+    End {
+        /// name of task
+        #[arg(required = true)]
+        task: String,
+        /// optional end time of task (HHMM)
+        #[arg(required = false)]
+        time: Option<String>,
+    },
+
+    /// end running tasks and start another at the same time.
+    /// every running task is ended unless --from is given.
+    Switch {
+        /// name of the task to start
+        task: String,
+        /// time switched (HHMM format)
+        time: Option<String>,
+        /// only end the running task with this name
+        #[arg(short, long)]
+        from: Option<String>,
+    },
+
+    /// get a list of tasks. List with no arguments returns a list of unended tasks
+    List {
+        #[command(flatten)]
+        query: QueryArgs,
+    },
+
+    /// amend the start or end time of a task
+    Fix {
+        /// name of task
+        #[arg(required = true)]
+        task: String,
+
+        /// days to search for task
+        #[arg(required = false)]
+        days: Option<i64>,
+
+        /// amend start time (HH:MM format)
+        #[arg(short, long, required = false)]
+        start: Option<String>,
+
+        /// amend end time (HH:MM format)
+        #[arg(short, long, required = false)]
+        end: Option<String>,
+    },
+
+    /// get the time spent per task. with no arguments, reports on today's tasks
+    Report {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        /// total the time per git branch instead of per task
+        #[arg(long)]
+        by_branch: bool,
+    },
+
+    /// print a snippet that tracks the task for the project directory the shell is in.
+    /// add it to the shell's startup file, e.g. `eval "$(timr hook bash)"` in ~/.bashrc.
+    /// directories are mapped to tasks in the `hook` section of the config
+    Hook {
+        #[arg(value_enum)]
+        shell: ShellArg,
+    },
+
+    /// print a completion script for `shell`, which also completes task names from the ledger.
+    /// e.g. `eval "$(timr completions bash)"` in ~/.bashrc
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// print the names of running and recent tasks, for shell completion
+    #[command(hide = true)]
+    CompleteTasks,
+
+    /// run by the shell hook each time a prompt is shown
+    #[command(hide = true)]
+    HookPrompt {
+        /// directory the shell is in
+        dir: PathBuf,
+        /// directory holding the ledger and config
+        #[arg(long)]
+        home: PathBuf,
+        /// when the previous prompt was shown, in seconds since the unix epoch
+        #[arg(long)]
+        last_prompt: Option<i64>,
+    },
+
+    /// list the git commits made while a task was running.
+    /// with no task, uses the most recent one
+    Commits {
+        /// name of task
+        task: Option<String>,
+        /// how many days back to look for the task. defaults to 7
+        days: Option<u32>,
+    },
+
+    /// export a timecard, with rounded and unrounded minutes.
+    /// with no arguments, exports today's tasks
+    Export {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        /// output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+    },
+
+    /// show running tasks, their elapsed time, and today's total
+    Status,
+
+    /// show overtime across weeks, from the targets in the config
+    Balance,
+
+    /// keep a live view of running tasks on screen, with keys to end, pause, and switch tasks
+    Watch,
+
+    /// browse and edit tasks full screen, by day or week: filter, rename, change start and
+    /// end times, delete, and start and end tasks
+    Ui,
+
+    /// upgrade the ledger to the current file format, keeping a copy of the old one
+    Migrate,
+
+    /// encrypt the ledger and its archive with a passphrase or key file. the secret is
+    /// taken from --key-file, the config, TIMR_PASSPHRASE or TIMR_KEY_FILE, or asked for
+    Encrypt {
+        /// file whose contents are the key
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+
+    /// decrypt the ledger and its archive back to plain text
+    Decrypt,
+
+    /// rewrite the ledger as a snapshot of its tasks. changes are appended to the ledger
+    /// as they happen, and compacting it makes reading it quicker
+    Compact,
+
+    /// move ended tasks into per-month or per-year archive files next to the ledger.
+    /// queries for their dates still find them
+    Archive {
+        /// archive tasks started before this day. defaults to `after_days` ago from the
+        /// `archive` config, or else the start of this month or year
+        #[arg(long)]
+        before: Option<String>,
+        /// split the archive by month or year, instead of `by` in the config
+        #[arg(long, value_enum)]
+        by: Option<PeriodArg>,
+        /// gzip the archive files
+        #[arg(long)]
+        compress: bool,
+    },
+
+    /// merge the ledger with a copy on another machine, e.g. in a synced folder or a git
+    /// repository, so that both have the same tasks. a task changed on both keeps the
+    /// version changed last
+    Sync {
+        /// the directory or ledger to sync with, or a git repository with --git.
+        /// defaults to `remote` in the `sync` config
+        remote: Option<String>,
+        /// clone the remote as a git repository, and push the merged ledger back to it
+        #[arg(long)]
+        git: bool,
+    },
+
+    /// serve a JSON API on localhost for starting and ending tasks, listing them, and
+    /// reports, until interrupted
+    Serve {
+        /// port to listen on, instead of `port` in the `serve` config
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// require `Authorization: Bearer <token>` on every request, instead of `token`
+        /// in the `serve` config
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// find running tasks that have gone on for longer than the `idle` limit in the config,
+    /// and offer to end them
+    Doctor {
+        /// end every stale task at the limit without asking
+        #[arg(long)]
+        close: bool,
+    },
+
+    /// run a pomodoro timer, logging each work interval as a task tagged `pomodoro`.
+    /// an interrupted session is resumed the next time this is run.
+    Pomodoro {
+        /// name to log work intervals under
+        #[arg(default_value = "pomodoro")]
+        task: String,
+
+        /// length of a work interval, in minutes
+        #[arg(short, long, default_value_t = 25)]
+        work: i64,
+
+        /// length of a short break, in minutes
+        #[arg(short = 'b', long = "break", default_value_t = 5)]
+        short_break: i64,
+
+        /// length of a long break, in minutes
+        #[arg(short, long, default_value_t = 15)]
+        long_break: i64,
+
+        /// number of work intervals to run
+        #[arg(short, long, default_value_t = 4)]
+        rounds: u32,
+
+        /// command to run when a phase ends, instead of ringing the terminal bell
+        #[arg(short, long)]
+        notify: Option<String>,
+    },
+
+    /// bill the ended tasks in a date range that have not been billed yet.
+    /// rates and rounding are set in the `billing` section of the config
+    Invoice {
+        /// first day to bill, e.g. 2023-10-01 or "last month". everything not billed yet
+        /// if not given
+        #[arg(short, long)]
+        from: Option<String>,
+        /// last day to bill, e.g. 2023-10-31 or "last month". today if not given
+        #[arg(short, long)]
+        to: Option<String>,
+        /// output format
+        #[arg(long, value_enum, default_value_t = InvoiceFormatArg::Text)]
+        format: InvoiceFormatArg,
+        /// show the invoice without recording it
+        #[arg(long)]
+        preview: bool,
+    },
+
+    /// add up times from a timecard, e.g. `calc 0800-1130 1215-1700`.
+    /// single times are paired up, and one left over runs until now (`calc 1630`)
+    Calc {
+        /// intervals (0800-1130) or times (24hr format, i.e 1630)
+        #[arg(required = true)]
+        times: Vec<String>,
+        /// break to subtract: an interval (1200-1230) or a length (30m, 1h). can be given more than once
+        #[arg(short, long = "break")]
+        breaks: Vec<String>,
+    },
+}
+
+impl Commands {
+    /// whether the command reads or writes the ledger, and so needs it unlocked and checked first.
+    fn uses_ledger(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Completions { .. } | Commands::Hook { .. } | Commands::Calc { .. }
+        )
+    }
+}
+
+/// reads a line from stdin, trimmed.
+fn prompt() -> anyhow::Result<String> {
+    let mut resp = String::new();
+    std::io::stdin().read_line(&mut resp)?;
+    Ok(resp.trim().to_string())
+}
+
+/// reads a passphrase from the terminal without showing it.
+fn read_passphrase(message: &str) -> anyhow::Result<String> {
+    use crossterm::event::{read, Event, KeyCode, KeyEventKind, KeyModifiers};
+    eprint!("{}", message);
+    crossterm::terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let read = loop {
+        match read() {
+            Err(e) => break Err(e.into()),
+            std::result::Result::Ok(Event::Key(k)) if k.kind == KeyEventKind::Press => match k.code
+            {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Backspace => _ = passphrase.pop(),
+                KeyCode::Char('c') if k.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("cancelled"))
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                _ => (),
+            },
+            _ => (),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+    read.map(|_| passphrase)
+}
+
+/// gives the library the secret for an encrypted ledger: a passphrase asked for, if there
+/// is no key file in the config and none in the environment either.
+fn unlock(timr: &Timr) -> anyhow::Result<()> {
+    let Some(enc) = schema::header_of(timr.file())?.and_then(|h| h.encryption) else {
+        return Ok(());
+    };
+    if encryption::secret_for(timr.file()).is_none() && std::io::stdin().is_terminal() {
+        let passphrase = read_passphrase(&format!("passphrase for {}: ", timr.file()))?;
+        encryption::set_secret(timr.file(), Secret::Passphrase(passphrase));
+    }
+    // fail here with a clear error, rather than part way through a command.
+    encryption::key_for(timr.file(), &enc).map(|_| ())
+}
+
+/// the tasks a report or export covers. defaults to today.
+fn report_query(args: &QueryArgs, timr: &Timr) -> anyhow::Result<Query> {
+    Ok(match args.to_query(timr)? {
+        Some(q) => q,
+        None => Query {
+            name: args.name.clone(),
+            tag: args.tag.clone(),
+            sort: args.sort.map(Sort::from),
+            limit: args.limit,
+            ..ListFilter::Today.into()
+        },
+    })
+}
+
+/// `minutes` in the configured duration style.
+fn duration(timr: &Timr, minutes: i64) -> String {
+    Duration::from_minutes(minutes).format(timr.config().duration_style)
+}
+
+/// `minutes`, followed by the rounded minutes if rounding is turned on.
+fn with_rounded(timr: &Timr, minutes: i64, rounded: i64) -> String {
+    match timr.config().rounding.rounding.increment > 1 {
+        true => format!(
+            "{} (rounded: {})",
+            duration(timr, minutes),
+            duration(timr, rounded)
+        ),
+        false => duration(timr, minutes),
+    }
+}
+
+/// formats minutes in the configured style with a leading + for overtime.
+fn signed(timr: &Timr, minutes: i64) -> String {
+    match minutes >= 0 {
+        true => format!("+{}", duration(timr, minutes)),
+        false => duration(timr, minutes),
+    }
+}
+
+/// counts down `minutes` in place on the current line.
+fn countdown(phase: Phase, minutes: i64) {
+    for left in (1..=minutes * 60).rev() {
+        print!("\r{}: {:02}:{:02} left ", phase, left / 60, left % 60);
+        _ = std::io::stdout().flush();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+    println!("\r{}: done.          ", phase);
+}
+
+/// warns about an old ledger format, ends tasks past their auto close time, archives old
+/// ones if the config asks for it, and warns about stale ones before running a command.
+fn check_running(timr: &Timr) -> anyhow::Result<()> {
+    let version = schema::version_of(timr.file())?;
+    if version < schema::VERSION {
+        eprintln!(
+            "{} is in an old format (version {}). run `timr migrate` to upgrade it.",
+            timr.file(),
+            version
+        );
+    }
+    for t in doctor::auto_close(timr)? {
+        let t = timr.localize(&t);
+        println!(
+            "{} was still running, ended at: {}",
+            t.task_name,
+            t.time_end.unwrap()
+        );
+    }
+    for (segment, count) in archive::auto_archive(timr)? {
+        eprintln!("archived {} task(s) into {}", count, segment.path.display());
+    }
+    let stale = doctor::stale_tasks(timr)?.len();
+    if stale > 0 {
+        eprintln!(
+            "{} task(s) have been running for more than {} hours. run `timr doctor` to end them.",
+            stale,
+            timr.config().idle.max_hours
+        );
+    }
+    Ok(())
+}
+
+pub fn do_parse() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let mut config = Config::load(CONFIG_FILE)?;
+    if let Some(style) = cli.style {
+        config.duration_style = style.into();
+    }
+    let timr = Timr::default().with_config(config);
+    // completions run while typing, and cannot ask for a passphrase.
+    let asks_for_passphrase = match cli.command.as_ref().filter(|c| c.uses_ledger()) {
+        Some(Commands::CompleteTasks | Commands::HookPrompt { .. } | Commands::Encrypt { .. }) => {
+            false
+        }
+        other => other.is_some(),
+    };
+    if asks_for_passphrase {
+        unlock(&timr)?;
+    }
+    match cli.command.as_ref().filter(|c| c.uses_ledger()) {
+        None
+        | Some(
+            Commands::Doctor { .. }
+            | Commands::Migrate
+            | Commands::Encrypt { .. }
+            | Commands::Decrypt
+            | Commands::HookPrompt { .. }
+            | Commands::CompleteTasks,
+        ) => (),
+        _ => check_running(&timr)?,
+    }
+    // You can check for the existence of subcommands, and if found use their
+    // matches just as you would the top level cmd
+    match &cli.command {
+        Some(Commands::Start {
+            task,
+            time,
+            project,
+            tags,
+        }) => {
+            let template = match task {
+                Some(task) => Task::new(String::new(), task.to_owned(), String::new(), None, 0),
+                None => timr.branch_task(&std::env::current_dir()?)?,
+            };
+            if timr.is_running(&template.task_name)? {
+                println!("\nthere is already a incomplete task with that name. \ndo you wish to create a new task? Y/N");
+                match prompt()?.to_lowercase().as_str() {
+                    "y" | "yes" => (),
+                    "n" | "no" => {
+                        println!("task canceled.");
+                        std::process::exit(0);
+                    }
+                    _ => {
+                        println!("invalid input, cancelling new task.");
+                        std::process::exit(0);
+                    }
+                }
+            }
+            let mut t = template;
+            t.tags.extend(tags.iter().cloned());
+            t.project = project.clone();
+            let t = timr.localize(&timr.start_task(t, time.as_deref())?);
+            println!("{} started at: {}", t.task_name, t.time_start);
+        }
+        Some(Commands::Log {
+            task,
+            date,
+            from,
+            to,
+            duration,
+            project,
+            tags,
+            allow_overlap,
+        }) => {
+            let today = timr.today();
+            let times = LogTimes {
+                date: match date {
+                    Some(d) => Some(parse_range(d, today, timr.config().week_start)?.0),
+                    None => None,
+                },
+                from: from.clone(),
+                to: to.clone(),
+                minutes: match duration {
+                    Some(d) => Some(d.parse::<Duration>()?.minutes()),
+                    None => None,
+                },
+            };
+            let mut t = Task::new(String::new(), task.to_owned(), String::new(), None, 0);
+            t.tags = tags.clone();
+            t.project = project.clone();
+            let t = timr.localize(&timr.log(t, &times, *allow_overlap)?);
+            println!(
+                "{} {}",
+                t.date.clone(),
+                t.print(timr.config().duration_style).unwrap()
+            );
+        }
+
+        Some(Commands::Fill { week, from, to }) => {
+            let today = timr.today();
+            let range = |s: &str| parse_range(s, today, timr.config().week_start);
+            let (from, to) = match (week, from) {
+                (true, _) => match timr.date_range(ListFilter::Week)? {
+                    Some((Some(from), to)) => (from, to),
+                    _ => unreachable!("a week always has a first day"),
+                },
+                (false, Some(from)) => (
+                    range(from)?.0,
+                    to.as_deref().map(range).transpose()?.map_or(today, |r| r.1),
+                ),
+                (false, None) => unreachable!("--from is required without --week"),
+            };
+            let filled = timr.fill(from, to)?;
+            if filled.is_empty() {
+                println!("nothing to fill from {} to {}.", from, to);
+            }
+            for t in filled.iter().map(|t| timr.localize(t)) {
+                println!(
+                    "{} {}",
+                    t.date.clone(),
+                    t.print(timr.config().duration_style).unwrap()
+                );
+            }
+        }
+
+        Some(Commands::Continue { task }) => {
+            let t = timr.localize(&timr.continue_task(task.as_deref())?);
+            println!("{} started at: {}", t.task_name, t.time_start);
+        }
+        Some(Commands::End { task, time }) => {
+            // end last task matching that does not have a end time.
+            let t = timr.localize(&timr.stop(task, time.as_deref())?);
+            println!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+        }
+
+        Some(Commands::Switch { task, time, from }) => {
+            let (ended, started) = timr.switch(task, from.as_deref(), time.as_deref())?;
+            let started = timr.localize(&started);
+            for t in ended.iter().map(|t| timr.localize(t)) {
+                println!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+            }
+            println!("{} started at: {}", started.task_name, started.time_start);
+        }
+
+        Some(Commands::Fix {
+            task,
+            days,
+            start,
+            end,
+        }) => {
+            let days = match days {
+                Some(d) if *d >= 1 => *d as i32,
+                _ => 7,
+            };
+            let mut tasks = timr.find(task, days)?;
+
+            let t = match tasks.len() {
+                0 => {
+                    println!("no task named {} found in the last {} days.", task, days);
+                    return Ok(());
+                }
+                1 => tasks.remove(0),
+                _ => {
+                    println!("please choose which task named {} to modify:", task);
+                    for (count, t) in tasks.iter().enumerate() {
+                        println!(
+                            "{}. {} \t {} \t {}",
+                            count + 1,
+                            t.task_name,
+                            t.date,
+                            t.time_start
+                        );
+                    }
+                    // get index from user
+                    match prompt()?.parse::<usize>() {
+                        std::result::Result::Ok(i) if i >= 1 && i <= tasks.len() => {
+                            tasks.remove(i - 1)
+                        }
+                        _ => {
+                            println!("invalid input, no task was changed.");
+                            return Ok(());
+                        }
+                    }
+                }
+            };
+            let t = timr.amend(&t, start.as_deref(), end.as_deref())?;
+            println!(
+                "{}",
+                timr.localize(&t)
+                    .print(timr.config().duration_style)
+                    .unwrap()
+            );
+        }
+
+        Some(Commands::List { query }) => {
+            // with no range, list the running tasks.
+            let query = match query.to_query(&timr)? {
+                Some(q) => q,
+                None => Query {
+                    name: query.name.clone(),
+                    tag: query.tag.clone(),
+                    sort: query.sort.map(Sort::from),
+                    limit: query.limit,
+                    ..ListFilter::Running.into()
+                },
+            };
+            for t in timr.query(&query)? {
+                println!(
+                    "{}",
+                    timr.localize(&t)
+                        .print(timr.config().duration_style)
+                        .unwrap()
+                );
+            }
+        }
+
+        Some(Commands::Report { query, by_branch }) => {
+            let report = timr.report(&report_query(query, &timr)?)?;
+            let totals = match by_branch {
+                true => &report.branches,
+                false => &report.totals,
+            };
+            for t in totals {
+                println!("{}: {}", t.name, with_rounded(&timr, t.minutes, t.rounded));
+            }
+            let unbranched: i64 = report
+                .tasks
+                .iter()
+                .filter(|t| t.branch.is_none())
+                .map(|t| t.time_total)
+                .sum();
+            if *by_branch && unbranched > 0 {
+                println!("no branch: {}", duration(&timr, unbranched));
+            }
+            if report.days.len() > 1 || timr.config().rounding.per == RoundingScope::Day {
+                println!();
+                for d in &report.days {
+                    println!("{}: {}", d.name, with_rounded(&timr, d.minutes, d.rounded));
+                }
+            }
+            println!(
+                "total: {}",
+                with_rounded(&timr, report.total, report.rounded_total)
+            );
+        }
+
+        Some(Commands::Completions { shell }) => {
+            let mut cmd = <Cli as clap::CommandFactory>::command();
+            print!("{}", crate::completions::script(*shell, &mut cmd)?);
+        }
+
+        Some(Commands::CompleteTasks) => {
+            for name in timr.task_names(30)? {
+                println!("{}", name);
+            }
+        }
+
+        Some(Commands::Hook { shell }) => {
+            let exe = std::env::current_exe()?;
+            let home = std::env::current_dir()?;
+            print!("{}", hook::snippet((*shell).into(), &exe, &home));
+        }
+
+        Some(Commands::HookPrompt {
+            dir,
+            home,
+            last_prompt,
+        }) => {
+            let timr = Timr::new(home.join(OUTPUT_FILE).to_string_lossy())
+                .with_config(Config::load(&home.join(CONFIG_FILE).to_string_lossy())?);
+            let last_prompt = last_prompt
+                .and_then(|s| chrono::DateTime::from_timestamp(s, 0))
+                .map(|d| d.fixed_offset());
+            let prompted = hook::on_prompt(&timr, dir, last_prompt)?;
+            for t in prompted.ended.iter().map(|t| timr.localize(t)) {
+                println!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+            }
+            if let Some(t) = prompted.started.map(|t| timr.localize(&t)) {
+                println!("{} started at: {}", t.task_name, t.time_start);
+            }
+        }
+
+        Some(Commands::Commits { task, days }) => {
+            let days = days.unwrap_or(7) as i32;
+            let t = match task {
+                Some(task) => timr.find(task, days)?.into_iter().next(),
+                None => timr.list(ListFilter::Days(days))?.into_iter().next(),
+            };
+            let t = match t {
+                Some(t) => t,
+                None => {
+                    println!("no task found in the last {} days.", days);
+                    return Ok(());
+                }
+            };
+            let commits = timr.commits(&t)?;
+            let local = timr.localize(&t);
+            println!(
+                "{} on {}, {} to {}:",
+                local.task_name,
+                local.date,
+                local.time_start,
+                local.time_end.unwrap_or("now".to_string())
+            );
+            if commits.is_empty() {
+                println!("no commits.");
+            }
+            for c in commits {
+                println!(
+                    "{} {} {}",
+                    &c.hash[..7.min(c.hash.len())],
+                    c.author,
+                    c.summary
+                );
+            }
+        }
+
+        Some(Commands::Export { query, format }) => {
+            let report = timr.report(&report_query(query, &timr)?)?;
+            match format {
+                ExportFormat::Csv => {
+                    let rounding = &timr.config().rounding;
+                    print!(
+                        "{}",
+                        report.to_csv(rounding.per, rounding, timr.config().duration_style)
+                    );
+                }
+                ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+        }
+
+        Some(Commands::Status) => {
+            let status = timr.status()?;
+            if status.running.is_empty() {
+                println!("no running tasks.");
+            }
+            for (t, elapsed) in status.running {
+                let t = timr.localize(&t);
+                println!(
+                    "{}: Started: {}, running for {}",
+                    t.task_name,
+                    t.time_start,
+                    duration(&timr, elapsed)
+                );
+            }
+            match timr.config().targets.is_set() {
+                true => {
+                    println!(
+                        "today: {} of {}",
+                        duration(&timr, status.today_total),
+                        duration(&timr, status.today_target)
+                    );
+                    println!(
+                        "this week: {} of {}",
+                        duration(&timr, status.week_total),
+                        duration(&timr, status.week_target)
+                    );
+                }
+                false => println!("today: {}", duration(&timr, status.today_total)),
+            }
+        }
+
+        Some(Commands::Balance) => {
+            if !timr.config().targets.is_set() {
+                println!(
+                    "no targets are set. add a `targets` section to {}.",
+                    CONFIG_FILE
+                );
+                return Ok(());
+            }
+            let balance = timr.balance()?;
+            for w in &balance.weeks {
+                println!(
+                    "week of {}: worked {}, target {}, {}",
+                    w.week_start,
+                    duration(&timr, w.worked),
+                    duration(&timr, w.target),
+                    signed(&timr, w.worked - w.target)
+                );
+            }
+            println!("balance: {}", signed(&timr, balance.balance));
+        }
+
+        Some(Commands::Watch) => crate::watch::run(&timr)?,
+        Some(Commands::Ui) => crate::ui::run(&timr)?,
+
+        Some(Commands::Migrate) => {
+            let m = schema::migrate(timr.file())?;
+            match m.backup {
+                Some(backup) => println!(
+                    "migrated {} task(s) in {} from version {} to {}. the old file is at {}",
+                    m.tasks,
+                    timr.file(),
+                    m.from,
+                    m.to,
+                    backup.display()
+                ),
+                None => println!("{} is already at version {}.", timr.file(), m.to),
+            }
+        }
+
+        Some(Commands::Encrypt { key_file }) => {
+            let given = match key_file {
+                Some(k) => Some(Secret::KeyFile(k.clone())),
+                None => timr
+                    .config()
+                    .encryption
+                    .secret()
+                    .or(encryption::secret_for(timr.file())),
+            };
+            let secret = match given {
+                Some(s) => s,
+                None => {
+                    let passphrase = read_passphrase("new passphrase: ")?;
+                    if passphrase.is_empty() {
+                        anyhow::bail!("the passphrase cannot be empty");
+                    }
+                    if read_passphrase("again: ")? != passphrase {
+                        anyhow::bail!("the passphrases do not match, nothing was encrypted");
+                    }
+                    Secret::Passphrase(passphrase)
+                }
+            };
+            let (count, removed) = encryption::encrypt(timr.file(), secret)?;
+            println!(
+                "encrypted {} task(s) in {}. without the passphrase or key file it cannot be read.",
+                count,
+                timr.file()
+            );
+            for backup in removed {
+                println!(
+                    "removed {}, an unencrypted copy left by `timr migrate`.",
+                    backup.display()
+                );
+            }
+        }
+
+        Some(Commands::Decrypt) => {
+            let count = encryption::decrypt(timr.file())?;
+            println!("decrypted {} task(s) in {}.", count, timr.file());
+        }
+
+        Some(Commands::Sync { remote, git }) => {
+            let remote = sync::Remote::of(remote.as_deref(), *git, &timr.config().sync)?;
+            let report = sync::sync(&timr, &remote)?;
+            println!(
+                "synced {} task(s): {} pulled, {} pushed, {} conflict(s) resolved.",
+                report.tasks, report.pulled, report.pushed, report.conflicts
+            );
+        }
+
+        Some(Commands::Serve { port, token }) => {
+            let config = &timr.config().serve;
+            let port = port.unwrap_or(config.port);
+            let token = token.clone().or_else(|| config.token.clone());
+            let secured = token.is_some();
+            let server = Server::bind(timr.clone(), port, token)?;
+            println!(
+                "serving {} on http://127.0.0.1:{}{}",
+                timr.file(),
+                server.port(),
+                match secured {
+                    true => ", with a token",
+                    false => "",
+                }
+            );
+            server.run();
+        }
+
+        Some(Commands::Compact) => {
+            println!("compacted {} task(s) into {}", timr.compact()?, timr.file());
+        }
+
+        Some(Commands::Archive {
+            before,
+            by,
+            compress,
+        }) => {
+            let mut config = timr.config().archive.clone();
+            config.by = by.map_or(config.by, Period::from);
+            config.compress |= *compress;
+            let today = timr.today();
+            let before = match before {
+                Some(d) => parse_range(d, today, timr.config().week_start)?.0,
+                None => archive::default_before(&config, today),
+            };
+            let written = archive::archive(&timr, before, &config)?;
+            if written.is_empty() {
+                println!("nothing to archive from before {}.", before);
+            }
+            for (segment, count) in written {
+                println!("archived {} task(s) into {}", count, segment.path.display());
+            }
+        }
+
+        Some(Commands::Doctor { close }) => {
+            for t in doctor::auto_close(&timr)? {
+                let t = timr.localize(&t);
+                println!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+            }
+            let stale = doctor::stale_tasks(&timr)?;
+            if stale.is_empty() {
+                println!("no stale tasks.");
+            }
+            for (t, elapsed) in stale {
+                let local = timr.localize(&t);
+                println!(
+                    "{} started {} at {}, running for {}",
+                    local.task_name,
+                    local.date,
+                    local.time_start,
+                    duration(&timr, elapsed)
+                );
+                let ended = match close {
+                    true => doctor::close_at_max(&timr, &t)?,
+                    false => {
+                        println!("end it at (HHMM), [m]ax length, or leave it running with enter:");
+                        match prompt()?.to_lowercase().as_str() {
+                            "" => continue,
+                            "m" | "max" => doctor::close_at_max(&timr, &t)?,
+                            time => timr.close(&t, timr.time_during(&t, time)?)?,
+                        }
+                    }
+                };
+                let ended = timr.localize(&ended);
+                println!("{} ended at: {}", ended.task_name, ended.time_end.unwrap());
+            }
+        }
+
+        Some(Commands::Pomodoro {
+            task,
+            work,
+            short_break,
+            long_break,
+            rounds,
+            notify,
+        }) => {
+            let pomodoro = Pomodoro {
+                task_name: task.clone(),
+                work: *work,
+                short_break: *short_break,
+                long_break: *long_break,
+                rounds: *rounds,
+                notify: notify.clone(),
+                ..Default::default()
+            };
+
+            let mut resumed = pomodoro.resume(&timr)?;
+            for (phase, minutes) in pomodoro.phases() {
+                let minutes = match (phase, resumed.take()) {
+                    (Phase::Work, Some(left)) => {
+                        println!("resuming {} with {} minutes left", task, left);
+                        left
+                    }
+                    (Phase::Work, None) => {
+                        let t = timr.localize(&pomodoro.start_work(&timr)?);
+                        println!("{} started at: {}", t.task_name, t.time_start);
+                        minutes
+                    }
+                    _ => minutes,
+                };
+                countdown(phase, minutes);
+                if phase == Phase::Work {
+                    let t = timr.localize(&pomodoro.finish_work(&timr)?);
+                    println!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+                }
+                pomodoro.notify(&format!("{} is over", phase))?;
+            }
+        }
+
+        Some(Commands::Invoice {
+            from,
+            to,
+            format,
+            preview,
+        }) => {
+            let today = timr.today();
+            let range = |s: &str| parse_range(s, today, timr.config().week_start);
+            let from = match from {
+                Some(d) => Some(range(d)?.0),
+                None => None,
+            };
+            let to = match to {
+                Some(d) => range(d)?.1,
+                None => today,
+            };
+            let invoice = timr.invoice(from, to, *preview)?;
+            print!("{}", invoice.render((*format).into()));
+        }
+
+        Some(Commands::Calc { times, breaks }) => {
+            let now = timr.now().time();
+            let result = calc::calculate(times, breaks, now)?;
+            let line = |minutes: i64| {
+                let d = Duration::from_minutes(minutes);
+                let also = match timr.config().duration_style {
+                    DurationStyle::Decimal => d.format(DurationStyle::Hmm),
+                    _ => format!("{} hours", d.format(DurationStyle::Decimal)),
+                };
+                format!(
+                    "{} ({}, {} minutes)",
+                    duration(&timr, minutes),
+                    also,
+                    minutes
+                )
+            };
+            if result.pieces.len() > 1 || !result.breaks.is_empty() {
+                for (piece, minutes) in &result.pieces {
+                    println!("{}: {}", piece, line(*minutes));
+                }
+                for (piece, minutes) in &result.breaks {
+                    println!("break {}: -{}", piece, line(*minutes));
+                }
+            }
+            println!("total: {}", line(result.total));
+
+            let rounding = timr.config().rounding.rounding;
+            if rounding.increment > 1 {
+                println!("rounded: {}", line(rounding.apply(result.total)));
+            }
+        }
+
+        //? should we do something if nothing is entered?
+        None => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,553 @@
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDate, Timelike};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use std::io::{Stdout, Write};
+use timr::util::duration;
+use timr::{util::utility::*, ListFilter, Query, Sort, Task, Timr};
+
+/// how much of the calendar is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Day,
+    Week,
+}
+
+/// a field of a task that can be edited in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Start,
+    End,
+}
+
+/// what the keys being typed are for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// typing the name filter, which is applied as it is typed.
+    Filter,
+    /// editing a field of the selected task, starting from its current value.
+    Edit(Field, String),
+    /// typing the name of a task to start.
+    Start(String),
+    /// waiting for `y` to delete the selected task.
+    ConfirmDelete,
+}
+
+/// state of the ui screen between keys.
+struct Ui {
+    view: View,
+    /// the day shown, or a day in the week shown.
+    day: NaiveDate,
+    /// only tasks whose name contains this are shown.
+    filter: String,
+    mode: Mode,
+    /// the tasks of the week `day` is in, oldest first.
+    week: Vec<Task>,
+    /// index into the tasks shown.
+    selected: usize,
+    /// result of the last action.
+    message: String,
+}
+
+/// runs the ui screen until the user quits.
+pub fn run(timr: &Timr) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = ui_loop(timr, &mut stdout);
+
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn ui_loop(timr: &Timr, stdout: &mut Stdout) -> Result<()> {
+    let mut ui = Ui::new(timr)?;
+    loop {
+        let rows = terminal::size()?.1 as usize;
+        draw(stdout, &ui.lines(timr, rows.saturating_sub(10).max(1)))?;
+
+        // redraw every few seconds, so running tasks keep up.
+        if !event::poll(std::time::Duration::from_secs(5))? {
+            ui.load(timr)?;
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(k) if k.kind == KeyEventKind::Press => k.code,
+            _ => continue,
+        };
+        if ui.key(timr, key)? {
+            return Ok(());
+        }
+    }
+}
+
+/// `minutes` in the configured duration style.
+fn length(timr: &Timr, minutes: i64) -> String {
+    duration::Duration::from_minutes(minutes).format(timr.config().duration_style)
+}
+
+/// a time typed in as `%H%M`, e.g. "930" is "0930".
+fn typed_time(input: &str) -> Result<String> {
+    match parse_time(input) {
+        Ok(t) => Ok(t.format("%H%M").to_string()),
+        Err(_) => bail!("{} is not a time. use HHMM, e.g. 0930", input.trim()),
+    }
+}
+
+/// a bar of the 24 hours of the day, with the hours `task` ran in filled in.
+fn timeline(task: &Task, now: Option<i64>) -> String {
+    let minutes = |s: &str| {
+        parse_time(s)
+            .ok()
+            .map(|t| (t.hour() * 60 + t.minute()) as i64)
+    };
+    let start = minutes(&task.time_start).unwrap_or(0);
+    let end = match task.time_end.as_deref().and_then(minutes).or(now) {
+        // ran past midnight.
+        Some(end) if end < start => 24 * 60,
+        Some(end) => end,
+        None => start,
+    };
+    (0..24)
+        .map(|h| match start < (h + 1) * 60 && end > h * 60 {
+            true => '█',
+            false => '·',
+        })
+        .collect()
+}
+
+impl Ui {
+    fn new(timr: &Timr) -> Result<Self> {
+        let mut ui = Self {
+            view: View::Day,
+            day: timr.today(),
+            filter: String::new(),
+            mode: Mode::Browse,
+            week: Vec::new(),
+            selected: 0,
+            message: String::new(),
+        };
+        ui.load(timr)?;
+        Ok(ui)
+    }
+
+    /// the first day of the week shown.
+    fn week_start(&self, timr: &Timr) -> NaiveDate {
+        week_start(self.day, timr.config().week_start)
+    }
+
+    /// reads the tasks of the week again.
+    fn load(&mut self, timr: &Timr) -> Result<()> {
+        let first = self.week_start(timr);
+        self.week = timr.query(&Query {
+            filter: ListFilter::Between(Some(first), Some(first + Duration::days(6))),
+            name: match self.filter.trim().is_empty() {
+                true => None,
+                false => Some(self.filter.trim().to_string()),
+            },
+            sort: Some(Sort::Date),
+            ..Default::default()
+        })?;
+        self.selected = self.selected.min(self.shown().len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// the tasks in the list: the day's, or the week's.
+    fn shown(&self) -> Vec<&Task> {
+        self.week
+            .iter()
+            .filter(|t| self.view == View::Week || t.naive_date() == Some(self.day))
+            .collect()
+    }
+
+    fn current(&self) -> Option<Task> {
+        self.shown().get(self.selected).map(|t| (*t).clone())
+    }
+
+    /// selects `task` if it is shown.
+    fn select(&mut self, task: &Task) {
+        if let Some(i) = self.shown().iter().position(|t| *t == task) {
+            self.selected = i;
+        }
+    }
+
+    /// handles a key. returns `true` once the user quits.
+    fn key(&mut self, timr: &Timr, key: KeyCode) -> Result<bool> {
+        if self.mode == Mode::Browse && matches!(key, KeyCode::Char('q') | KeyCode::Esc) {
+            return Ok(true);
+        }
+        match self.act(timr, key) {
+            Ok(Some(task)) => {
+                self.load(timr)?;
+                self.select(&task);
+            }
+            Ok(None) => self.load(timr)?,
+            Err(e) => {
+                self.message = e.to_string();
+                self.load(timr)?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// does what `key` asks for. returns the task it changed, if it should be selected.
+    fn act(&mut self, timr: &Timr, key: KeyCode) -> Result<Option<Task>> {
+        let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+        match mode {
+            Mode::Browse => self.browse(timr, key),
+            Mode::Filter => {
+                match key {
+                    KeyCode::Char(c) => self.filter.push(c),
+                    KeyCode::Backspace => _ = self.filter.pop(),
+                    KeyCode::Esc => self.filter.clear(),
+                    KeyCode::Enter => return Ok(None),
+                    _ => (),
+                }
+                if key != KeyCode::Esc {
+                    self.mode = Mode::Filter;
+                }
+                self.selected = 0;
+                Ok(None)
+            }
+            Mode::Edit(field, mut input) => match key {
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Edit(field, input);
+                    Ok(None)
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Edit(field, input);
+                    Ok(None)
+                }
+                KeyCode::Enter => self.edit(timr, field, &input).map(Some),
+                _ => Ok(None),
+            },
+            Mode::Start(mut input) => match key {
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Start(input);
+                    Ok(None)
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Start(input);
+                    Ok(None)
+                }
+                KeyCode::Enter if !input.trim().is_empty() => {
+                    let t = timr.start(input.trim(), None)?;
+                    self.day = timr.today();
+                    self.message = format!("started {}", t.task_name);
+                    Ok(Some(t))
+                }
+                _ => Ok(None),
+            },
+            Mode::ConfirmDelete => {
+                let Some(t) = self.current() else {
+                    return Ok(None);
+                };
+                match key {
+                    KeyCode::Char('y') => {
+                        timr.delete(&t)?;
+                        self.message = format!("deleted {}", t.task_name);
+                    }
+                    _ => self.message = format!("kept {}", t.task_name),
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn browse(&mut self, timr: &Timr, key: KeyCode) -> Result<Option<Task>> {
+        let rows = self.shown().len();
+        let step = match self.view {
+            View::Day => Duration::days(1),
+            View::Week => Duration::days(7),
+        };
+        let current = self.current();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(rows.saturating_sub(1))
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.day -= step;
+                self.selected = 0;
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.day += step;
+                self.selected = 0;
+            }
+            KeyCode::Char('t') => {
+                self.day = timr.today();
+                self.selected = 0;
+            }
+            KeyCode::Char('v') => {
+                self.view = match self.view {
+                    View::Day => View::Week,
+                    View::Week => View::Day,
+                };
+                return Ok(current);
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('s') => self.mode = Mode::Start(String::new()),
+            KeyCode::Char('r')
+            | KeyCode::Char('b')
+            | KeyCode::Char('f')
+            | KeyCode::Char('d')
+            | KeyCode::Char('e')
+                if current.is_none() =>
+            {
+                self.message = "there is no task here".to_string();
+            }
+            KeyCode::Char('r') => {
+                let t = current.unwrap();
+                self.mode = Mode::Edit(Field::Name, t.task_name);
+            }
+            KeyCode::Char('b') => {
+                let t = current.unwrap();
+                self.mode = Mode::Edit(Field::Start, timr.localize(&t).time_start);
+            }
+            KeyCode::Char('f') => {
+                let t = current.unwrap();
+                self.mode = Mode::Edit(Field::End, timr.localize(&t).time_end.unwrap_or_default());
+            }
+            KeyCode::Char('d') => self.mode = Mode::ConfirmDelete,
+            KeyCode::Char('e') => {
+                let t = current.unwrap();
+                if t.time_end.is_some() {
+                    bail!("{} has already ended", t.task_name);
+                }
+                let t = timr.close(&t, timr.now())?;
+                let local = timr.localize(&t);
+                self.message = format!(
+                    "{} ended at: {}",
+                    t.task_name,
+                    local.time_end.unwrap_or_default()
+                );
+                return Ok(Some(t));
+            }
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    /// sets `field` of the selected task to `input`. changing a time works out its total again.
+    fn edit(&mut self, timr: &Timr, field: Field, input: &str) -> Result<Task> {
+        let Some(t) = self.current() else {
+            bail!("there is no task here");
+        };
+        let t = match field {
+            Field::Name => timr.rename(&t, input)?,
+            Field::Start => timr.amend(&t, Some(&typed_time(input)?), None)?,
+            Field::End => timr.amend(&t, None, Some(&typed_time(input)?))?,
+        };
+        let local = timr.localize(&t);
+        self.message = format!(
+            "{}: {}-{} {}",
+            t.task_name,
+            local.time_start,
+            local.time_end.unwrap_or_default(),
+            length(timr, t.time_total)
+        );
+        Ok(t)
+    }
+
+    /// the screen, with at most `rows` tasks listed.
+    fn lines(&self, timr: &Timr, rows: usize) -> Vec<String> {
+        let first = self.week_start(timr);
+        let today = timr.today();
+        let now = timr.now();
+        let minutes = |t: &Task| match t.time_end {
+            Some(_) => t.time_total,
+            None => task_elapsed(t, timr.config().timezone).map_or(0, |d| d.num_minutes()),
+        };
+
+        let mut lines = vec![match self.view {
+            View::Day => format!("timr ui  {}", self.day.format("%A %Y-%m-%d")),
+            View::Week => format!("timr ui  week of {}", first.format("%Y-%m-%d")),
+        }];
+        if !self.filter.is_empty() {
+            lines[0] += &format!("  (name contains \"{}\")", self.filter);
+        }
+        lines.push(String::new());
+
+        // the week as a calendar, with the total for each day.
+        let days: Vec<String> = first
+            .iter_days()
+            .take(7)
+            .map(|d| {
+                let total: i64 = self
+                    .week
+                    .iter()
+                    .filter(|t| t.naive_date() == Some(d))
+                    .map(minutes)
+                    .sum();
+                let cell = format!("{} {:>6}", d.format("%a %d"), length(timr, total));
+                match (d == self.day, d == today) {
+                    (true, _) => format!("[{}]", cell),
+                    (false, true) => format!("*{}*", cell),
+                    (false, false) => format!(" {} ", cell),
+                }
+            })
+            .collect();
+        lines.push(days.join(" "));
+        lines.push(String::new());
+
+        let shown = self.shown();
+        if shown.is_empty() {
+            lines.push("  no tasks.".to_string());
+        }
+        let skip = self.selected.saturating_sub(rows.saturating_sub(1));
+        for (i, t) in shown.iter().enumerate().skip(skip).take(rows) {
+            let running_now = match t.naive_date() == Some(today) && t.time_end.is_none() {
+                true => Some((now.hour() * 60 + now.minute()) as i64),
+                false => None,
+            };
+            let local = timr.localize(t);
+            let when = match self.view {
+                View::Day => timeline(&local, running_now),
+                View::Week => local
+                    .naive_date()
+                    .map_or(local.date.clone(), |d| d.format("%a %d").to_string()),
+            };
+            let mut line = format!(
+                "{} {}  {}-{:<4} {:>8}  {}",
+                if i == self.selected { ">" } else { " " },
+                when,
+                local.time_start,
+                local.time_end.clone().unwrap_or_else(|| "...".to_string()),
+                length(timr, minutes(t)),
+                t.task_name
+            );
+            for tag in &t.tags {
+                line += &format!(" #{}", tag);
+            }
+            lines.push(line);
+        }
+        lines.push(String::new());
+        let total: i64 = shown.iter().map(|t| minutes(t)).sum();
+        lines.push(format!("total: {}", length(timr, total)));
+        lines.push(String::new());
+
+        let current = self.current();
+        lines.push(match &self.mode {
+            Mode::Browse => "[←/→] day or week  [v]iew  [t]oday  [/]filter  [r]ename  [b]egin  [f]inish  [d]elete  [s]tart  [e]nd  [q]uit".to_string(),
+            Mode::Filter => format!("filter: {}_", self.filter),
+            Mode::Edit(Field::Name, input) => format!("name: {}_", input),
+            Mode::Edit(Field::Start, input) => format!("start (HHMM): {}_", input),
+            Mode::Edit(Field::End, input) => format!("end (HHMM): {}_", input),
+            Mode::Start(input) => format!("start task: {}_", input),
+            Mode::ConfirmDelete => match current {
+                Some(t) => {
+                    let local = timr.localize(&t);
+                    format!("delete {} on {} at {}? [y/n]", t.task_name, local.date, local.time_start)
+                }
+                None => String::new(),
+            },
+        });
+        lines.push(self.message.clone());
+        lines
+    }
+}
+
+fn draw(stdout: &mut Stdout, lines: &[String]) -> Result<()> {
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16))?;
+        if row == 0 {
+            queue!(
+                stdout,
+                SetAttribute(Attribute::Bold),
+                Print(line),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(stdout, Print(line))?;
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_file;
+
+    fn press(ui: &mut Ui, timr: &Timr, keys: &str) {
+        for c in keys.chars() {
+            assert!(!ui.key(timr, KeyCode::Char(c)).unwrap());
+        }
+    }
+
+    fn retype(ui: &mut Ui, timr: &Timr, value: &str) {
+        for _ in 0..10 {
+            ui.key(timr, KeyCode::Backspace).unwrap();
+        }
+        press(ui, timr, value);
+        ui.key(timr, KeyCode::Enter).unwrap();
+    }
+
+    #[test]
+    fn test_edit_and_delete() {
+        let timr = Timr::new(temp_file("ui_edit"));
+        timr.start("writing", Some("0900")).unwrap();
+        timr.start("review", Some("0700")).unwrap();
+        timr.stop("review", Some("0730")).unwrap();
+        let mut ui = Ui::new(&timr).unwrap();
+        assert_eq!(ui.shown().len(), 2);
+        assert_eq!(ui.current().unwrap().task_name, "review");
+
+        // finishing the running task works out its total.
+        press(&mut ui, &timr, "jf");
+        retype(&mut ui, &timr, "1030");
+        assert_eq!(ui.current().unwrap().time_total, 90);
+        press(&mut ui, &timr, "b");
+        retype(&mut ui, &timr, "800");
+        let t = ui.current().unwrap();
+        assert_eq!((t.time_start.as_str(), t.time_total), ("0800", 150));
+        press(&mut ui, &timr, "r");
+        retype(&mut ui, &timr, "writing docs");
+        assert_eq!(ui.current().unwrap().task_name, "writing docs");
+        press(&mut ui, &timr, "b");
+        retype(&mut ui, &timr, "noon");
+        assert!(ui.message.contains("not a time"));
+
+        press(&mut ui, &timr, "/docs");
+        ui.key(&timr, KeyCode::Enter).unwrap();
+        assert_eq!(ui.shown().len(), 1);
+        press(&mut ui, &timr, "/");
+        ui.key(&timr, KeyCode::Esc).unwrap();
+        assert_eq!(ui.shown().len(), 2);
+
+        // nothing is deleted without a `y`.
+        press(&mut ui, &timr, "kdn");
+        assert_eq!(timr.list(ListFilter::Today).unwrap().len(), 2);
+        press(&mut ui, &timr, "dy");
+        assert_eq!(timr.list(ListFilter::Today).unwrap().len(), 1);
+        assert_eq!(ui.shown().len(), 1);
+
+        press(&mut ui, &timr, "s");
+        press(&mut ui, &timr, "email");
+        ui.key(&timr, KeyCode::Enter).unwrap();
+        assert!(timr.is_running("email").unwrap());
+        assert_eq!(ui.current().unwrap().task_name, "email");
+        press(&mut ui, &timr, "e");
+        assert!(!timr.is_running("email").unwrap());
+        assert!(ui
+            .lines(&timr, 10)
+            .iter()
+            .any(|l| l.contains("writing docs")));
+        assert!(ui.key(&timr, KeyCode::Char('q')).unwrap());
+    }
+}
@@ -0,0 +1,535 @@
+use super::{
+    encryption::Lines,
+    events::{self, TaskKey},
+    schema::{self, Header},
+    service::Timr,
+    tasks::Task,
+    utility::*,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// How archived tasks are split into files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// one file per month, e.g. `timr.2023-10.json`.
+    #[default]
+    Month,
+    /// one file per year, e.g. `timr.2023.json`.
+    Year,
+}
+
+/// Archiving settings, the `archive` section of the config.
+///
+/// # Example
+/// ```json
+/// "archive": { "by": "year", "after_days": 90, "compress": true }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    pub by: Period,
+    /// archive ended tasks older than this many days whenever timr is run.
+    /// nothing is archived automatically if this is not set.
+    pub after_days: Option<i64>,
+    /// gzip the archive files.
+    pub compress: bool,
+}
+
+/// A file of archived tasks next to the ledger, holding the tasks of one month or year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub path: PathBuf,
+    /// the first and last days the segment can hold tasks for.
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    /// the ledger the tasks were archived from, whose secret unlocks an encrypted segment.
+    ledger: String,
+}
+
+impl Segment {
+    pub fn is_compressed(&self) -> bool {
+        self.path.extension().is_some_and(|e| e == "gz")
+    }
+
+    /// whether the segment can hold tasks from `from` to `to`.
+    pub fn overlaps(&self, from: Option<NaiveDate>, to: NaiveDate) -> bool {
+        self.first <= to && from.is_none_or(|f| self.last >= f)
+    }
+
+    pub fn read(&self) -> Result<Vec<Task>> {
+        let mut s = String::new();
+        let f = std::fs::File::open(&self.path)?;
+        match self.is_compressed() {
+            true => GzDecoder::new(f).read_to_string(&mut s),
+            false => std::io::BufReader::new(f).read_to_string(&mut s),
+        }
+        .with_context(|| format!("error reading archive {}", self.path.display()))?;
+        events::replay(&s, &self.ledger)
+    }
+
+    /// writes `tasks` to the segment, encrypted if the ledger is.
+    pub fn write(&self, tasks: &[Task]) -> Result<()> {
+        let header = Header {
+            encryption: schema::header_of(&self.ledger)?.and_then(|h| h.encryption),
+            ..Header::current()
+        };
+        let lines = Lines::of(&header, &self.ledger)?;
+        let mut buf = header.to_json_string();
+        for t in tasks {
+            buf += &lines.seal(&serde_json::to_string(t)?);
+            buf.push('\n');
+        }
+        let f = std::fs::File::create(&self.path)?;
+        match self.is_compressed() {
+            true => {
+                let mut gz = GzEncoder::new(f, Compression::default());
+                gz.write_all(buf.as_bytes())?;
+                gz.finish()?;
+            }
+            false => std::io::BufWriter::new(f).write_all(buf.as_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+/// the directory and file name stem of the ledger, e.g. (".", "timr") for `timr.json`.
+fn ledger_parts(file: &str) -> (PathBuf, String) {
+    let path = Path::new(file);
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (dir, stem)
+}
+
+/// the days a segment key like "2023" or "2023-10" covers.
+fn key_range(key: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match key.split_once('-') {
+        None if key.len() == 4 => {
+            let year = key.parse().ok()?;
+            Some((
+                NaiveDate::from_ymd_opt(year, 1, 1)?,
+                NaiveDate::from_ymd_opt(year, 12, 31)?,
+            ))
+        }
+        Some((y, m)) if y.len() == 4 && m.len() == 2 => {
+            let first = NaiveDate::from_ymd_opt(y.parse().ok()?, m.parse().ok()?, 1)?;
+            let next = match first.month() {
+                12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)?,
+                m => NaiveDate::from_ymd_opt(first.year(), m + 1, 1)?,
+            };
+            Some((first, next - Duration::days(1)))
+        }
+        _ => None,
+    }
+}
+
+/// the key of the segment `date` is archived in.
+fn key_for(date: NaiveDate, by: Period) -> String {
+    match by {
+        Period::Month => format!("{}-{:02}", date.year(), date.month()),
+        Period::Year => date.year().to_string(),
+    }
+}
+
+fn segment(file: &str, key: &str, compress: bool) -> Option<Segment> {
+    let (_, stem) = ledger_parts(file);
+    let (first, last) = key_range(key)?;
+    let ext = if compress { "json.gz" } else { "json" };
+    Some(Segment {
+        path: Path::new(file).with_file_name(format!("{}.{}.{}", stem, key, ext)),
+        first,
+        last,
+        ledger: file.to_string(),
+    })
+}
+
+/// the archive segments of the ledger `file`, newest first.
+pub fn segments(file: &str) -> Result<Vec<Segment>> {
+    let (dir, stem) = ledger_parts(file);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}.", stem);
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        let rest = match name.strip_prefix(&prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let (key, compress) = match (rest.strip_suffix(".json.gz"), rest.strip_suffix(".json")) {
+            (Some(key), _) => (key, true),
+            (None, Some(key)) => (key, false),
+            _ => continue,
+        };
+        if let Some(s) = segment(file, key, compress) {
+            found.push(s);
+        }
+    }
+    found.sort_by_key(|s| std::cmp::Reverse((s.first, s.last)));
+    Ok(found)
+}
+
+/// reads the archived tasks started between `from` and `to`, both days included.
+/// only the segments covering those days are read.
+pub fn read_archived(from: Option<NaiveDate>, to: NaiveDate, file: &str) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+    for s in segments(file)?.iter().filter(|s| s.overlaps(from, to)) {
+        tasks.extend(s.read()?.into_iter().filter(|t| match t.naive_date() {
+            Some(d) => from.is_none_or(|f| d >= f) && d <= to,
+            None => false,
+        }));
+    }
+    Ok(tasks)
+}
+
+/// marks the archived tasks among `billed` with the invoice `number`, rewriting
+/// only the segments covering `from` to `to`.
+pub fn mark_invoiced(
+    billed: &[Task],
+    number: &str,
+    (from, to): (Option<NaiveDate>, NaiveDate),
+    now: DateTime<FixedOffset>,
+    file: &str,
+) -> Result<()> {
+    for s in segments(file)?.iter().filter(|s| s.overlaps(from, to)) {
+        let mut tasks = s.read()?;
+        let mut marked = false;
+        for t in tasks
+            .iter_mut()
+            .filter(|t| t.invoice.is_none() && billed.contains(t))
+        {
+            t.invoice = Some(number.to_string());
+            t.touch(now);
+            marked = true;
+        }
+        if marked {
+            s.write(&tasks)?;
+        }
+    }
+    Ok(())
+}
+
+/// replaces the archived task `old` with `new`, or removes it if there is no `new`,
+/// rewriting only the segment it is in. returns whether the task was found.
+pub fn replace_archived(old: &TaskKey, new: Option<Task>, file: &str) -> Result<bool> {
+    let date = match NaiveDate::parse_from_str(&old.date, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => return Ok(false),
+    };
+    for s in segments(file)?
+        .iter()
+        .filter(|s| s.overlaps(Some(date), date))
+    {
+        let mut tasks = s.read()?;
+        if let Some(i) = tasks.iter().position(|t| old.matches(t)) {
+            match new {
+                Some(t) => tasks[i] = t,
+                None => _ = tasks.remove(i),
+            }
+            s.write(&tasks)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// the default cutoff for `timr archive`: `after_days` ago, or else the start of
+/// the current month or year, so that only whole periods are archived.
+pub fn default_before(config: &ArchiveConfig, today: NaiveDate) -> NaiveDate {
+    match (config.after_days, config.by) {
+        (Some(days), _) => today - Duration::days(days),
+        (None, Period::Month) => today.with_day(1).unwrap(),
+        (None, Period::Year) => today.with_ordinal(1).unwrap(),
+    }
+}
+
+/// moves the ended tasks started before `before` out of the ledger and into archive segments.
+/// while billing rates are set, tasks that have not been billed yet stay in the ledger.
+///
+/// segments written with another period or compression that share days with the new ones
+/// are split up into them, so that changing the config loses nothing.
+///
+/// returns each segment tasks were moved into, with the number of tasks added to it.
+pub fn archive(
+    timr: &Timr,
+    before: NaiveDate,
+    config: &ArchiveConfig,
+) -> Result<Vec<(Segment, usize)>> {
+    let billing = &timr.config().billing;
+    let billed = |t: &Task| {
+        t.invoice.is_some() || (billing.default_rate.is_none() && billing.rates.is_empty())
+    };
+
+    let mut keep = Vec::new();
+    let mut moving: BTreeMap<String, Vec<Task>> = BTreeMap::new();
+    for t in read_all_tasks(timr.file())? {
+        match t.naive_date() {
+            Some(d) if d < before && t.time_end.is_some() && billed(&t) => {
+                moving.entry(key_for(d, config.by)).or_default().push(t)
+            }
+            _ => keep.push(t),
+        }
+    }
+    if moving.is_empty() {
+        return Ok(Vec::new());
+    }
+    let added: BTreeMap<String, usize> = moving.iter().map(|(k, v)| (k.clone(), v.len())).collect();
+
+    // read in every segment that shares days with one about to be written. splitting a year
+    // into months can reach more segments, so this goes on until no more are found.
+    let mut existing: Vec<(Segment, bool)> = segments(timr.file())?
+        .into_iter()
+        .map(|s| (s, false))
+        .collect();
+    loop {
+        let targets: Vec<Segment> = moving
+            .keys()
+            .filter_map(|k| segment(timr.file(), k, config.compress))
+            .collect();
+        let mut found = false;
+        for (s, read) in existing.iter_mut().filter(|(_, read)| !read) {
+            if !targets.iter().any(|t| s.overlaps(Some(t.first), t.last)) {
+                continue;
+            }
+            for t in s.read()? {
+                match t.naive_date() {
+                    Some(d) => moving.entry(key_for(d, config.by)).or_default().push(t),
+                    None => keep.push(t),
+                }
+            }
+            *read = true;
+            found = true;
+        }
+        if !found {
+            break;
+        }
+    }
+
+    let mut written = Vec::new();
+    let mut paths = Vec::new();
+    for (key, mut tasks) in moving.into_iter().rev() {
+        let target = segment(timr.file(), &key, config.compress).unwrap();
+        tasks.sort_by_key(|t| std::cmp::Reverse((t.naive_date(), parse_time(&t.time_start).ok())));
+        target.write(&tasks)?;
+        paths.push(target.path.clone());
+        if let Some(&count) = added.get(&key) {
+            written.push((target, count));
+        }
+    }
+    for (s, _) in existing
+        .iter()
+        .filter(|(s, read)| *read && !paths.contains(&s.path))
+    {
+        std::fs::remove_file(&s.path)?;
+    }
+    write_all_tasks(&keep, timr.file())?;
+    Ok(written)
+}
+
+/// archives with the config's policy, if `after_days` is set.
+pub fn auto_archive(timr: &Timr) -> Result<Vec<(Segment, usize)>> {
+    let config = &timr.config().archive;
+    match config.after_days {
+        Some(_) => archive(timr, default_before(config, timr.today()), config),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::config::Config;
+    use crate::util::testing::temp_dir;
+    use crate::ListFilter;
+
+    fn temp_timr(name: &str, archive: ArchiveConfig) -> Timr {
+        let dir = temp_dir(name);
+        let config = Config {
+            archive,
+            ..Default::default()
+        };
+        Timr::new(dir.join("timr.json").to_string_lossy()).with_config(config)
+    }
+
+    fn ended(timr: &Timr, name: &str, date: &str) {
+        let t = Task::new(
+            date.to_string(),
+            name.to_string(),
+            "0900".to_string(),
+            Some("1000".to_string()),
+            60,
+        );
+        output_task_to_file(t, timr.file()).unwrap();
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_key_range() {
+        assert_eq!(
+            key_range("2023"),
+            Some((date("2023-01-01"), date("2023-12-31")))
+        );
+        assert_eq!(
+            key_range("2024-02"),
+            Some((date("2024-02-01"), date("2024-02-29")))
+        );
+        assert_eq!(
+            key_range("2023-12"),
+            Some((date("2023-12-01"), date("2023-12-31")))
+        );
+        assert_eq!(key_range("backup"), None);
+        assert_eq!(key_range("2023-1"), None);
+    }
+
+    #[test]
+    fn test_archive_by_month() {
+        let timr = temp_timr("archive_month", ArchiveConfig::default());
+        ended(&timr, "september", "2023-9-28");
+        ended(&timr, "october", "2023-10-2");
+        ended(&timr, "late october", "2023-10-30");
+        ended(&timr, "november", "2023-11-6");
+        timr.start("running", None).unwrap();
+
+        let config = ArchiveConfig::default();
+        let written = archive(&timr, date("2023-11-01"), &config).unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[0].0.path.file_name().unwrap(), "timr.2023-10.json");
+        assert_eq!(written[0].1, 2);
+
+        let live = read_all_tasks(timr.file()).unwrap();
+        assert_eq!(live.len(), 2);
+        assert!(timr.is_running("running").unwrap());
+
+        // queries still see archived tasks, reading only the segments they need.
+        let october = read_tasks_between(Some(date("2023-10-01")), date("2023-10-31"), timr.file());
+        assert_eq!(october.unwrap().len(), 2);
+        let all = timr.query(&ListFilter::Between(None, None).into()).unwrap();
+        assert_eq!(all.len(), 5);
+
+        // archiving again adds to the same segment.
+        ended(&timr, "early november", "2023-11-1");
+        let written = archive(&timr, date("2023-12-01"), &config).unwrap();
+        assert_eq!(written[0].1, 2);
+        assert_eq!(segments(timr.file()).unwrap()[0].read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_archive_switching_period() {
+        let timr = temp_timr("archive_switch", ArchiveConfig::default());
+        ended(&timr, "september", "2023-9-28");
+        ended(&timr, "october", "2023-10-2");
+        archive(&timr, date("2023-11-01"), &ArchiveConfig::default()).unwrap();
+        assert_eq!(segments(timr.file()).unwrap().len(), 2);
+
+        // the months are merged into the year.
+        let by_year = ArchiveConfig {
+            by: Period::Year,
+            ..Default::default()
+        };
+        ended(&timr, "november", "2023-11-6");
+        let written = archive(&timr, date("2023-12-01"), &by_year).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].1, 1);
+        let found = segments(timr.file()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path.file_name().unwrap(), "timr.2023.json");
+        assert_eq!(found[0].read().unwrap().len(), 3);
+
+        // and the year is split back up into months.
+        ended(&timr, "december", "2023-12-4");
+        let written = archive(&timr, date("2024-01-01"), &ArchiveConfig::default()).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].0.path.file_name().unwrap(), "timr.2023-12.json");
+        let found = segments(timr.file()).unwrap();
+        assert_eq!(found.len(), 4);
+        assert!(found.iter().all(|s| s.read().unwrap().len() == 1));
+        let all = read_tasks_between(None, date("2023-12-31"), timr.file()).unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn test_archive_compressed_by_year() {
+        let config = ArchiveConfig {
+            by: Period::Year,
+            after_days: Some(30),
+            compress: true,
+        };
+        let timr = temp_timr("archive_year", config);
+        ended(&timr, "last year", "2022-12-30");
+        ended(&timr, "new year", "2023-1-2");
+        ended(&timr, "today", &format_date(timr.today()));
+
+        let written = auto_archive(&timr).unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].0.path.file_name().unwrap(), "timr.2022.json.gz");
+        assert!(written[1].0.is_compressed());
+        assert_eq!(read_all_tasks(timr.file()).unwrap().len(), 1);
+
+        let old = read_tasks_between(None, date("2022-12-31"), timr.file()).unwrap();
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].task_name, "last year");
+        assert!(auto_archive(&timr).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invoice_archived() {
+        // archived before billing was set up, so nothing has been billed.
+        let timr = temp_timr("archive_invoice", ArchiveConfig::default());
+        ended(&timr, "september", "2023-9-28");
+        ended(&timr, "october", "2023-10-2");
+        archive(&timr, date("2023-10-01"), &ArchiveConfig::default()).unwrap();
+
+        let mut config = timr.config().clone();
+        config.billing.default_rate = Some(60.0);
+        let timr = Timr::new(timr.file()).with_config(config);
+        let invoice = timr.invoice(None, date("2023-10-31"), false).unwrap();
+        assert_eq!(invoice.tasks.len(), 2);
+
+        let archived = segments(timr.file()).unwrap()[0].read().unwrap();
+        assert_eq!(
+            archived[0].invoice.as_deref(),
+            Some(invoice.number.as_str())
+        );
+        assert!(timr.invoice(None, date("2023-10-31"), false).is_err());
+    }
+
+    #[test]
+    fn test_edit_archived() {
+        let timr = temp_timr("archive_edit", ArchiveConfig::default());
+        ended(&timr, "september", "2023-9-28");
+        ended(&timr, "october", "2023-10-2");
+        archive(&timr, date("2023-11-01"), &ArchiveConfig::default()).unwrap();
+        assert!(read_all_tasks(timr.file()).unwrap().is_empty());
+
+        let october = ListFilter::Between(Some(date("2023-10-01")), Some(date("2023-10-31")));
+        let t = timr.list(october).unwrap().remove(0);
+        let t = timr.rename(&t, "planning").unwrap();
+        let t = timr.amend(&t, Some("0830"), None).unwrap();
+        assert_eq!(timr.list(october).unwrap(), vec![t.clone()]);
+        assert_eq!(t.time_total, 90);
+        // the change stays in the archive.
+        assert!(read_all_tasks(timr.file()).unwrap().is_empty());
+
+        timr.delete(&t).unwrap();
+        assert!(timr.list(october).unwrap().is_empty());
+        assert!(timr.delete(&t).is_err());
+        let all = timr.query(&ListFilter::Between(None, None).into()).unwrap();
+        assert_eq!(all.len(), 1);
+    }
+}
@@ -0,0 +1,343 @@
+use super::{rounding::Rounding, tasks::Task};
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// An hourly rate, applied to tasks matching `task`, `tag`, or `project`.
+/// a rate with none of them set matches nothing.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rate {
+    pub task: Option<String>,
+    pub tag: Option<String>,
+    pub project: Option<String>,
+    pub rate: f64,
+}
+
+/// Billing settings, the `billing` section of the config.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Billing {
+    pub currency: String,
+    /// rate for tasks that no entry in `rates` matches.
+    pub default_rate: Option<f64>,
+    pub rates: Vec<Rate>,
+    /// rounding applied to each line item before it is billed.
+    pub rounding: Rounding,
+    /// invoice numbers are this prefix followed by a counter, e.g. "INV-0001".
+    pub invoice_prefix: String,
+}
+
+impl Default for Billing {
+    fn default() -> Self {
+        Self {
+            currency: "USD".to_string(),
+            default_rate: None,
+            rates: Vec::new(),
+            rounding: Rounding::default(),
+            invoice_prefix: "INV-".to_string(),
+        }
+    }
+}
+
+impl Billing {
+    /// the hourly rate for `task`. a rate for the task name wins over a rate for one of its tags,
+    /// which wins over a rate for its project.
+    pub fn rate_for(&self, task: &Task) -> Option<f64> {
+        let by_task = self
+            .rates
+            .iter()
+            .find(|r| r.task.as_deref() == Some(task.task_name.as_str()));
+        let by_tag = || {
+            self.rates
+                .iter()
+                .find(|r| r.tag.as_deref().is_some_and(|tag| task.has_tag(tag)))
+        };
+        let by_project = || {
+            self.rates
+                .iter()
+                .find(|r| r.project.is_some() && r.project == task.project)
+        };
+        match by_task.or_else(by_tag).or_else(by_project) {
+            Some(r) => Some(r.rate),
+            None => self.default_rate,
+        }
+    }
+
+    /// the invoice number that follows the highest one already used in `tasks`.
+    pub fn next_invoice_number(&self, tasks: &[Task]) -> String {
+        let last = tasks
+            .iter()
+            .filter_map(|t| t.invoice.as_deref())
+            .filter_map(|n| n.strip_prefix(self.invoice_prefix.as_str()))
+            .filter_map(|n| n.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0);
+        format!("{}{:04}", self.invoice_prefix, last + 1)
+    }
+
+    /// builds an invoice from `tasks`, one line item per task name and rate.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if a task has no rate and there is no default rate,
+    /// or if a task has already been billed.
+    pub fn invoice(
+        &self,
+        number: String,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        tasks: Vec<Task>,
+    ) -> Result<Invoice> {
+        let mut items: Vec<LineItem> = Vec::new();
+        for t in &tasks {
+            if let Some(n) = &t.invoice {
+                bail!("{} on {} was already billed on {}", t.task_name, t.date, n);
+            }
+            let rate = match self.rate_for(t) {
+                Some(r) => r,
+                None => bail!("no rate is configured for {}", t.task_name),
+            };
+            match items
+                .iter_mut()
+                .find(|i| i.description == t.task_name && i.rate == rate)
+            {
+                Some(item) => item.minutes += t.time_total,
+                None => items.push(LineItem {
+                    description: t.task_name.clone(),
+                    minutes: t.time_total,
+                    rate,
+                    ..Default::default()
+                }),
+            }
+        }
+        for item in items.iter_mut() {
+            item.billed_minutes = self.rounding.apply(item.minutes);
+            item.amount = round_cents(item.billed_minutes as f64 / 60.0 * item.rate);
+        }
+        let total = round_cents(items.iter().map(|i| i.amount).sum());
+
+        Ok(Invoice {
+            number,
+            from,
+            to,
+            currency: self.currency.clone(),
+            items,
+            total,
+            tasks,
+        })
+    }
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct LineItem {
+    pub description: String,
+    /// minutes actually logged.
+    pub minutes: i64,
+    /// minutes billed, after rounding.
+    pub billed_minutes: i64,
+    /// hourly rate.
+    pub rate: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvoiceFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Invoice {
+    pub number: String,
+    /// start of the billed period. `None` bills everything not billed yet up to `to`.
+    pub from: Option<NaiveDate>,
+    pub to: NaiveDate,
+    pub currency: String,
+    pub items: Vec<LineItem>,
+    pub total: f64,
+    /// the tasks that were billed.
+    pub tasks: Vec<Task>,
+}
+
+impl Invoice {
+    fn period(&self) -> String {
+        match self.from {
+            Some(from) => format!("{} to {}", from, self.to),
+            None => format!("up to {}", self.to),
+        }
+    }
+
+    pub fn render(&self, format: InvoiceFormat) -> String {
+        let hours = |m: i64| format!("{}:{:02}", m / 60, m % 60);
+        let mut s = String::new();
+        match format {
+            InvoiceFormat::Text => {
+                s += &format!("Invoice {}\n{}\n\n", self.number, self.period());
+                for i in &self.items {
+                    s += &format!(
+                        "{:<30} {:>7} @ {:>8.2} = {:>10.2}\n",
+                        i.description,
+                        hours(i.billed_minutes),
+                        i.rate,
+                        i.amount
+                    );
+                }
+                s += &format!("\n{:<30} {:>30.2} {}\n", "Total", self.total, self.currency);
+            }
+            InvoiceFormat::Markdown => {
+                s += &format!("# Invoice {}\n\n{}\n\n", self.number, self.period());
+                s += "| Task | Hours | Rate | Amount |\n|---|---:|---:|---:|\n";
+                for i in &self.items {
+                    s += &format!(
+                        "| {} | {} | {:.2} | {:.2} |\n",
+                        i.description.replace('|', "\\|"),
+                        hours(i.billed_minutes),
+                        i.rate,
+                        i.amount
+                    );
+                }
+                s += &format!(
+                    "| **Total** | | | **{:.2} {}** |\n",
+                    self.total, self.currency
+                );
+            }
+            InvoiceFormat::Html => {
+                s += &format!(
+                    "<h1>Invoice {}</h1>\n<p>{}</p>\n<table>\n",
+                    escape_html(&self.number),
+                    self.period()
+                );
+                s += "<tr><th>Task</th><th>Hours</th><th>Rate</th><th>Amount</th></tr>\n";
+                for i in &self.items {
+                    s += &format!(
+                        "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td></tr>\n",
+                        escape_html(&i.description),
+                        hours(i.billed_minutes),
+                        i.rate,
+                        i.amount
+                    );
+                }
+                s += &format!(
+                    "<tr><th>Total</th><td></td><td></td><th>{:.2} {}</th></tr>\n</table>\n",
+                    self.total,
+                    escape_html(&self.currency)
+                );
+            }
+        }
+        s
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rounding::RoundingMode;
+
+    fn task(name: &str, minutes: i64) -> Task {
+        Task::new(
+            "2023-10-20".to_string(),
+            name.to_string(),
+            "0900".to_string(),
+            Some("1000".to_string()),
+            minutes,
+        )
+    }
+
+    fn billing() -> Billing {
+        Billing {
+            default_rate: Some(50.0),
+            rates: vec![
+                Rate {
+                    tag: Some("urgent".to_string()),
+                    rate: 150.0,
+                    ..Default::default()
+                },
+                Rate {
+                    task: Some("debugging".to_string()),
+                    rate: 100.0,
+                    ..Default::default()
+                },
+                Rate {
+                    project: Some("acme".to_string()),
+                    rate: 80.0,
+                    ..Default::default()
+                },
+            ],
+            rounding: Rounding::new(15, RoundingMode::Up),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rate_for() {
+        let b = billing();
+        let mut t = task("debugging", 60);
+        t.tags = vec!["urgent".to_string()];
+        t.project = Some("acme".to_string());
+        assert_eq!(b.rate_for(&t), Some(100.0));
+
+        let mut t = task("meeting", 60);
+        t.tags = vec!["urgent".to_string()];
+        t.project = Some("acme".to_string());
+        assert_eq!(b.rate_for(&t), Some(150.0));
+        t.tags.clear();
+        assert_eq!(b.rate_for(&t), Some(80.0));
+        t.project = None;
+        assert_eq!(b.rate_for(&t), Some(50.0));
+
+        let b = Billing::default();
+        assert_eq!(b.rate_for(&t), None);
+    }
+
+    #[test]
+    fn test_invoice() {
+        let b = billing();
+        let to = NaiveDate::from_ymd_opt(2023, 10, 31).unwrap();
+        let tasks = vec![
+            task("debugging", 50),
+            task("debugging", 20),
+            task("email", 10),
+        ];
+        let inv = b.invoice("INV-0001".to_string(), None, to, tasks).unwrap();
+
+        assert_eq!(inv.items.len(), 2);
+        assert_eq!(inv.items[0].minutes, 70);
+        assert_eq!(inv.items[0].billed_minutes, 75);
+        assert_eq!(inv.items[0].amount, 125.0);
+        assert_eq!(inv.items[1].amount, 12.5);
+        assert_eq!(inv.total, 137.5);
+
+        let md = inv.render(InvoiceFormat::Markdown);
+        assert!(md.contains("| debugging | 1:15 | 100.00 | 125.00 |"));
+        assert!(inv.render(InvoiceFormat::Html).contains("<td>email</td>"));
+
+        let mut billed = task("email", 10);
+        billed.invoice = Some("INV-0001".to_string());
+        assert!(b
+            .invoice("INV-0002".to_string(), None, to, vec![billed])
+            .is_err());
+    }
+
+    #[test]
+    fn test_next_invoice_number() {
+        let b = Billing::default();
+        assert_eq!(b.next_invoice_number(&[]), "INV-0001");
+        let mut t = task("email", 10);
+        t.invoice = Some("INV-0041".to_string());
+        assert_eq!(b.next_invoice_number(&[t]), "INV-0042");
+    }
+}
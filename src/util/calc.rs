@@ -0,0 +1,138 @@
+use super::{duration::Duration, utility::parse_time};
+use anyhow::{bail, Result};
+use chrono::NaiveTime;
+
+/// A span of time on a timecard, e.g. `0800-1130`.
+/// an end before the start runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Interval {
+    pub fn minutes(&self) -> i64 {
+        let m = (self.end - self.start).num_minutes();
+        match m < 0 {
+            true => m + 24 * 60,
+            false => m,
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H%M"),
+            self.end.format("%H%M")
+        )
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('-') {
+            Some((start, end)) => Ok(Self {
+                start: parse_time(start)?,
+                end: parse_time(end)?,
+            }),
+            None => bail!("{} is not an interval like 0800-1130", s),
+        }
+    }
+}
+
+/// The pieces of a timecard calculation and their total, all in minutes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Calculation {
+    /// each interval worked, with its length.
+    pub pieces: Vec<(String, i64)>,
+    /// each break taken, with its length.
+    pub breaks: Vec<(String, i64)>,
+    /// time worked less the breaks.
+    pub total: i64,
+}
+
+/// adds up the intervals in `times` and subtracts the `breaks`.
+///
+/// `times` holds intervals (`0800-1130`) or single times. single times are paired up in order,
+/// and one left over runs until `now`. `breaks` holds intervals or a length of time (`30`, `30m`, `1h`).
+pub fn calculate(times: &[String], breaks: &[String], now: NaiveTime) -> Result<Calculation> {
+    let mut intervals: Vec<Interval> = Vec::new();
+    let mut open: Option<NaiveTime> = None;
+    for t in times {
+        if t.contains('-') {
+            intervals.push(t.parse()?);
+            continue;
+        }
+        let time = parse_time(t)?;
+        match open.take() {
+            Some(start) => intervals.push(Interval { start, end: time }),
+            None => open = Some(time),
+        }
+    }
+    if let Some(start) = open {
+        intervals.push(Interval { start, end: now });
+    }
+
+    let mut calc = Calculation::default();
+    for i in intervals {
+        calc.total += i.minutes();
+        calc.pieces.push((i.to_string(), i.minutes()));
+    }
+    for b in breaks {
+        let minutes = match b.contains('-') {
+            true => b.parse::<Interval>()?.minutes(),
+            false => match b.parse::<Duration>() {
+                Ok(d) if d.minutes() >= 0 => d.minutes(),
+                _ => bail!("{} is not a break like 30m or 1200-1230", b),
+            },
+        };
+        calc.total -= minutes;
+        calc.breaks.push((b.clone(), minutes));
+    }
+    Ok(calc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_calculate() {
+        let now = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+
+        let calc = calculate(&strings(&["0800-1130", "1215-1700"]), &[], now).unwrap();
+        assert_eq!(calc.pieces[0], ("0800-1130".to_string(), 210));
+        assert_eq!(calc.pieces[1], ("1215-1700".to_string(), 285));
+        assert_eq!(calc.total, 495);
+
+        let calc = calculate(
+            &strings(&["0800", "1700"]),
+            &strings(&["30m", "1200-1215"]),
+            now,
+        );
+        assert_eq!(calc.unwrap().total, 540 - 45);
+        let calc = calculate(&strings(&["0800-1700"]), &strings(&["1h"]), now).unwrap();
+        assert_eq!(calc.total, 480);
+
+        // a single time runs until now.
+        assert_eq!(calculate(&strings(&["0930"]), &[], now).unwrap().total, 150);
+
+        // past midnight.
+        assert_eq!(
+            calculate(&strings(&["2300-0100"]), &[], now).unwrap().total,
+            120
+        );
+
+        assert!(calculate(&strings(&["0800-nope"]), &[], now).is_err());
+        assert!(calculate(&strings(&["0800-0900"]), &strings(&["lunch"]), now).is_err());
+    }
+}
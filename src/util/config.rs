@@ -0,0 +1,108 @@
+use super::{
+    archive::ArchiveConfig,
+    billing::Billing,
+    doctor::IdleConfig,
+    duration::DurationStyle,
+    encryption::EncryptionConfig,
+    git::GitConfig,
+    hook::HookConfig,
+    rounding::TimecardRounding,
+    server::ServeConfig,
+    sync::SyncConfig,
+    targets::Targets,
+    templates::{Recurring, Template},
+};
+use anyhow::{Context, Result};
+use chrono::Weekday;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const CONFIG_FILE: &str = "timr_config.json";
+
+/// User settings, read from [`CONFIG_FILE`]. every section is optional, so a missing
+/// file (or a file with only some sections) falls back to the defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// first day of the week, e.g. "mon" or "sunday".
+    pub week_start: Weekday,
+    /// time zone times are entered and shown in, e.g. "Europe/Berlin".
+    /// the system's local time zone is used if this is not set.
+    pub timezone: Option<Tz>,
+    /// how lengths of time are shown: "hmm" (2:30), "decimal" (2.50), "verbose", or "iso" (PT2H30M).
+    pub duration_style: DurationStyle,
+    pub billing: Billing,
+    pub rounding: TimecardRounding,
+    pub targets: Targets,
+    pub git: GitConfig,
+    pub hook: HookConfig,
+    pub idle: IdleConfig,
+    pub archive: ArchiveConfig,
+    pub encryption: EncryptionConfig,
+    pub sync: SyncConfig,
+    pub serve: ServeConfig,
+    /// tasks logged often, by the name used with `timr log`.
+    pub templates: BTreeMap<String, Template>,
+    /// templates logged on a schedule by `timr fill`.
+    pub recurring: Vec<Recurring>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            week_start: Weekday::Mon,
+            timezone: None,
+            duration_style: DurationStyle::Hmm,
+            billing: Billing::default(),
+            rounding: TimecardRounding::default(),
+            targets: Targets::default(),
+            git: GitConfig::default(),
+            hook: HookConfig::default(),
+            idle: IdleConfig::default(),
+            archive: ArchiveConfig::default(),
+            encryption: EncryptionConfig::default(),
+            sync: SyncConfig::default(),
+            serve: ServeConfig::default(),
+            templates: BTreeMap::new(),
+            recurring: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// reads the config from `file`, or the default config if there is no such file.
+    pub fn load(file: &str) -> Result<Self> {
+        if !Path::new(file).exists() {
+            return Ok(Self::default());
+        }
+        let s = std::fs::read_to_string(file)?;
+        serde_json::from_str(&s).with_context(|| format!("error parsing config file {}", file))
+    }
+
+    pub fn save(&self, file: &str) -> Result<()> {
+        Ok(std::fs::write(file, serde_json::to_string_pretty(self)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config() {
+        let config: Config = serde_json::from_str(r#"{"week_start": "sunday"}"#).unwrap();
+        assert_eq!(config.week_start, Weekday::Sun);
+        assert_eq!(config.billing, Billing::default());
+
+        let config: Config = serde_json::from_str(r#"{"timezone": "Europe/Berlin"}"#).unwrap();
+        assert_eq!(config.timezone, Some(chrono_tz::Europe::Berlin));
+
+        let config: Config = serde_json::from_str(r#"{"duration_style": "h:mm"}"#).unwrap();
+        assert_eq!(config.duration_style, DurationStyle::Hmm);
+
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, Config::default());
+    }
+}
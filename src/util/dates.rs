@@ -0,0 +1,129 @@
+use super::utility::week_start;
+use anyhow::{bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// the first and last day of the month `date` is in.
+fn month_of(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = date.with_day(1).unwrap();
+    let next = match first.month() {
+        12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m + 1, 1),
+    };
+    (first, next.unwrap() - Duration::days(1))
+}
+
+/// parses a date, or a span of dates, relative to `today`. returns the first and last day.
+///
+/// understands:
+/// * `2023-10-01`
+/// * `today`, `yesterday`
+/// * a weekday (`monday`, `fri`): the most recent one, today included
+/// * `N days ago`, `N weeks ago`
+/// * `this week`, `last week`, `this month`, `last month`, `this year`, `last year`
+pub fn parse_range(
+    s: &str,
+    today: NaiveDate,
+    first_day: Weekday,
+) -> Result<(NaiveDate, NaiveDate)> {
+    let s = s.trim().to_lowercase();
+    if let Ok(d) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return Ok((d, d));
+    }
+    if let Ok(day) = s.parse::<Weekday>() {
+        let d = week_start(today, day);
+        return Ok((d, d));
+    }
+
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let range = match words.as_slice() {
+        ["today"] => (today, today),
+        ["yesterday"] => {
+            let d = today - Duration::days(1);
+            (d, d)
+        }
+        [n, unit, "ago"] => {
+            let n: i64 = match n.parse() {
+                Ok(n) => n,
+                Err(_) => bail!("could not understand the date {}", s),
+            };
+            let d = match *unit {
+                "day" | "days" => today - Duration::days(n),
+                "week" | "weeks" => today - Duration::weeks(n),
+                _ => bail!("could not understand the date {}", s),
+            };
+            (d, d)
+        }
+        [which @ ("this" | "last"), unit] => {
+            let last = *which == "last";
+            match *unit {
+                "week" => {
+                    let mut start = week_start(today, first_day);
+                    if last {
+                        start -= Duration::days(7);
+                    }
+                    (start, start + Duration::days(6))
+                }
+                "month" => match last {
+                    true => month_of(month_of(today).0 - Duration::days(1)),
+                    false => month_of(today),
+                },
+                "year" => {
+                    let year = today.year() - last as i32;
+                    (
+                        NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+                        NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+                    )
+                }
+                _ => bail!("could not understand the date {}", s),
+            }
+        }
+        _ => bail!("could not understand the date {}", s),
+    };
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_range() {
+        // a wednesday
+        let today = date("2023-10-18");
+        let parse = |s| parse_range(s, today, Weekday::Mon).unwrap();
+        let day = |s| (date(s), date(s));
+
+        assert_eq!(parse("2023-10-01"), day("2023-10-01"));
+        assert_eq!(parse("today"), day("2023-10-18"));
+        assert_eq!(parse("Yesterday"), day("2023-10-17"));
+        assert_eq!(parse("monday"), day("2023-10-16"));
+        assert_eq!(parse("wed"), day("2023-10-18"));
+        assert_eq!(parse("thursday"), day("2023-10-12"));
+        assert_eq!(parse("3 days ago"), day("2023-10-15"));
+        assert_eq!(parse("1 week ago"), day("2023-10-11"));
+        assert_eq!(parse("this week"), (date("2023-10-16"), date("2023-10-22")));
+        assert_eq!(parse("last week"), (date("2023-10-09"), date("2023-10-15")));
+        assert_eq!(
+            parse("this month"),
+            (date("2023-10-01"), date("2023-10-31"))
+        );
+        assert_eq!(
+            parse("last month"),
+            (date("2023-09-01"), date("2023-09-30"))
+        );
+        assert_eq!(parse("last year"), (date("2022-01-01"), date("2022-12-31")));
+
+        let sunday = parse_range("last week", today, Weekday::Sun).unwrap();
+        assert_eq!(sunday, (date("2023-10-08"), date("2023-10-14")));
+
+        let january = parse_range("last month", date("2024-01-10"), Weekday::Mon).unwrap();
+        assert_eq!(january, (date("2023-12-01"), date("2023-12-31")));
+
+        assert!(parse_range("someday", today, Weekday::Mon).is_err());
+        assert!(parse_range("x days ago", today, Weekday::Mon).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use super::{service::Timr, tasks::Task, utility::*};
+use anyhow::Result;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Limits on running tasks, the `idle` section of the config.
+///
+/// # Example
+/// ```json
+/// "idle": { "max_hours": 10, "auto_close_at": "1800" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// a task running for longer than this is stale, and was probably forgotten.
+    pub max_hours: f64,
+    /// end running tasks at this time (HHMM) on the day they started,
+    /// the next time timr is run after it. tasks started later run until the next day's.
+    pub auto_close_at: Option<String>,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            max_hours: 12.0,
+            auto_close_at: None,
+        }
+    }
+}
+
+impl IdleConfig {
+    pub fn max_minutes(&self) -> i64 {
+        (self.max_hours * 60.0).round() as i64
+    }
+}
+
+/// the running tasks that have gone on for longer than `max_hours`, with their minutes so far.
+pub fn stale_tasks(timr: &Timr) -> Result<Vec<(Task, i64)>> {
+    let max = timr.config().idle.max_minutes();
+    let mut stale = Vec::new();
+    for t in read_incomplete_tasks(timr.file())? {
+        let elapsed = task_elapsed(&t, timr.config().timezone)?.num_minutes();
+        if elapsed > max {
+            stale.push((t, elapsed));
+        }
+    }
+    Ok(stale)
+}
+
+/// ends `task` `max_hours` after it started.
+pub fn close_at_max(timr: &Timr, task: &Task) -> Result<Task> {
+    let end = timr.time_during(task, &task.time_start)?;
+    timr.close(
+        task,
+        end + Duration::minutes(timr.config().idle.max_minutes()),
+    )
+}
+
+/// ends every running task whose `auto_close_at` time has passed. returns the ended tasks.
+pub fn auto_close(timr: &Timr) -> Result<Vec<Task>> {
+    let at = match &timr.config().idle.auto_close_at {
+        Some(at) => at,
+        None => return Ok(Vec::new()),
+    };
+    let now = timr.now();
+    let mut closed = Vec::new();
+    for t in read_incomplete_tasks(timr.file())? {
+        let end = timr.time_during(&t, at)?;
+        if end <= now {
+            closed.push(timr.close(&t, end)?);
+        }
+    }
+    Ok(closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::config::Config;
+    use crate::util::testing::temp_file;
+    use chrono_tz::Tz;
+
+    fn temp_timr(name: &str, idle: IdleConfig) -> Timr {
+        let config = Config {
+            timezone: Some(Tz::UTC),
+            idle,
+            ..Default::default()
+        };
+        Timr::new(temp_file(name)).with_config(config)
+    }
+
+    /// writes a running task that started `days` ago at `time`, like the ones left in old ledgers.
+    fn forgotten(timr: &Timr, name: &str, days: i64, time: &str) -> Task {
+        let date = timr.today() - Duration::days(days);
+        let t = Task::new(
+            format_date(date),
+            name.to_string(),
+            time.to_string(),
+            None,
+            0,
+        );
+        output_task_to_file(t.clone(), timr.file()).unwrap();
+        t
+    }
+
+    #[test]
+    fn test_stale_tasks() {
+        let timr = temp_timr("doctor_stale", IdleConfig::default());
+        let old = forgotten(&timr, "duplicate_task", 3, "1900");
+        timr.start("fresh", None).unwrap();
+
+        let stale = stale_tasks(&timr).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].0, old);
+
+        let closed = close_at_max(&timr, &old).unwrap();
+        assert_eq!(closed.time_end.as_deref(), Some("0700"));
+        assert_eq!(closed.time_total, 12 * 60);
+        assert!(stale_tasks(&timr).unwrap().is_empty());
+        assert!(timr.is_running("fresh").unwrap());
+    }
+
+    #[test]
+    fn test_auto_close() {
+        let idle = IdleConfig {
+            auto_close_at: Some("1800".to_string()),
+            ..Default::default()
+        };
+        let timr = temp_timr("doctor_auto_close", idle);
+        forgotten(&timr, "morning", 2, "0900");
+        forgotten(&timr, "evening", 2, "2000");
+
+        let closed = auto_close(&timr).unwrap();
+        assert_eq!(closed.len(), 2);
+        // the morning task ends that day, the evening one at the next day's close.
+        assert_eq!(closed[0].task_name, "evening");
+        assert_eq!(closed[0].time_total, 22 * 60);
+        assert_eq!(closed[1].time_total, 9 * 60);
+        assert!(auto_close(&timr).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+/// How a [`Duration`] is shown, the `duration_style` option in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationStyle {
+    /// hours and minutes, e.g. "2:30".
+    #[default]
+    #[serde(alias = "h:mm")]
+    Hmm,
+    /// decimal hours, e.g. "2.50".
+    Decimal,
+    /// e.g. "2 hours 30 minutes".
+    Verbose,
+    /// ISO 8601, e.g. "PT2H30M".
+    Iso,
+}
+
+/// A length of time, to the minute.
+///
+/// parses `2h30m`, `2.5h`, `150m`, `150`, `2:30`, `1 hour 5 minutes`, and `PT2H30M`.
+///
+/// # Example
+/// ```
+/// use timr::util::duration::{Duration, DurationStyle};
+///
+/// let d: Duration = "2.5h".parse()?;
+/// assert_eq!(d.minutes(), 150);
+/// assert_eq!(d.format(DurationStyle::Iso), "PT2H30M");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct Duration(i64);
+
+impl Duration {
+    pub fn from_minutes(minutes: i64) -> Self {
+        Self(minutes)
+    }
+
+    pub fn minutes(self) -> i64 {
+        self.0
+    }
+
+    pub fn format(self, style: DurationStyle) -> String {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let (h, m) = (self.0.abs() / 60, self.0.abs() % 60);
+        let plural = |n: i64, unit: &str| match n {
+            1 => format!("1 {}", unit),
+            n => format!("{} {}s", n, unit),
+        };
+        match style {
+            DurationStyle::Hmm => format!("{}{}:{:02}", sign, h, m),
+            DurationStyle::Decimal => format!("{:.2}", self.0 as f64 / 60.0),
+            DurationStyle::Verbose => match (h, m) {
+                (0, m) => format!("{}{}", sign, plural(m, "minute")),
+                (h, 0) => format!("{}{}", sign, plural(h, "hour")),
+                (h, m) => format!("{}{} {}", sign, plural(h, "hour"), plural(m, "minute")),
+            },
+            DurationStyle::Iso => match (h, m) {
+                (0, m) => format!("{}PT{}M", sign, m),
+                (h, 0) => format!("{}PT{}H", sign, h),
+                (h, m) => format!("{}PT{}H{}M", sign, h, m),
+            },
+        }
+    }
+}
+
+/// adds up numbers followed by an hour or minute unit, e.g. "2h 30m".
+/// a number on its own is minutes.
+fn parse_units(s: &str) -> Option<f64> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut minutes = 0.0;
+    while !rest.is_empty() {
+        let n = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..n].parse().ok()?;
+        rest = rest[n..].trim_start();
+        let u = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        minutes += match &rest[..u] {
+            "h" | "hr" | "hrs" | "hour" | "hours" => number * 60.0,
+            "" | "m" | "min" | "mins" | "minute" | "minutes" => number,
+            _ => return None,
+        };
+        rest = rest[u..].trim_start();
+    }
+    Some(minutes)
+}
+
+impl std::str::FromStr for Duration {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.trim().to_lowercase();
+        let (sign, unsigned) = match lower.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, lower.as_str()),
+        };
+        let minutes = match (unsigned.strip_prefix("pt"), unsigned.split_once(':')) {
+            (Some(iso), _) => parse_units(iso),
+            (None, Some((h, m))) => match (h.parse::<u32>(), m.parse::<u32>()) {
+                (Ok(hours), Ok(mins)) if mins < 60 && m.len() == 2 => hours
+                    .checked_mul(60)
+                    .and_then(|h| h.checked_add(mins))
+                    .map(|total| total as f64),
+                _ => None,
+            },
+            (None, None) => parse_units(unsigned),
+        };
+        match minutes {
+            Some(m) => Ok(Self(sign * m.round() as i64)),
+            None => bail!(
+                "{} is not a duration like 2h30m, 2.5h, 150m, or 2:30",
+                s.trim()
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(DurationStyle::Hmm))
+    }
+}
+
+impl From<chrono::Duration> for Duration {
+    fn from(d: chrono::Duration) -> Self {
+        Self(d.num_minutes())
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl std::iter::Sum for Duration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self(iter.map(|d| d.0).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(s: &str) -> i64 {
+        s.parse::<Duration>().unwrap().minutes()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(minutes("2h30m"), 150);
+        assert_eq!(minutes("2.5h"), 150);
+        assert_eq!(minutes("150m"), 150);
+        assert_eq!(minutes("150"), 150);
+        assert_eq!(minutes("2:30"), 150);
+        assert_eq!(minutes("2:05"), 125);
+        assert_eq!(minutes("1 hour 5 minutes"), 65);
+        assert_eq!(minutes("2H"), 120);
+        assert_eq!(minutes("PT2H30M"), 150);
+        assert_eq!(minutes("-0:30"), -30);
+        assert_eq!(minutes("45m"), 45);
+        assert_eq!(minutes("90"), 90);
+
+        for bad in ["", "soon", "2:60", "2:5:00", "h", "2x", "PT", "99999999:00"] {
+            assert!(bad.parse::<Duration>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let d = Duration::from_minutes(150);
+        assert_eq!(d.format(DurationStyle::Hmm), "2:30");
+        assert_eq!(d.format(DurationStyle::Decimal), "2.50");
+        assert_eq!(d.format(DurationStyle::Verbose), "2 hours 30 minutes");
+        assert_eq!(d.format(DurationStyle::Iso), "PT2H30M");
+
+        let hour = Duration::from_minutes(60);
+        assert_eq!(hour.format(DurationStyle::Verbose), "1 hour");
+        assert_eq!(hour.format(DurationStyle::Iso), "PT1H");
+
+        let zero = Duration::default();
+        assert_eq!(zero.format(DurationStyle::Verbose), "0 minutes");
+        assert_eq!(zero.format(DurationStyle::Iso), "PT0M");
+
+        let long = Duration::from_minutes(6000 + 5);
+        assert_eq!(long.format(DurationStyle::Hmm), "100:05");
+        assert_eq!(
+            Duration::from_minutes(125).format(DurationStyle::Decimal),
+            "2.08"
+        );
+
+        let late = Duration::from_minutes(-30);
+        assert_eq!(late.format(DurationStyle::Hmm), "-0:30");
+        assert_eq!(late.format(DurationStyle::Decimal), "-0.50");
+        assert_eq!(late.format(DurationStyle::Iso), "-PT30M");
+
+        for style in [
+            DurationStyle::Hmm,
+            DurationStyle::Verbose,
+            DurationStyle::Iso,
+        ] {
+            let s = d.format(style);
+            assert_eq!(s.parse::<Duration>().unwrap(), d, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use super::{
+    archive,
+    hook::expand_home,
+    schema::{self, Header},
+    utility::{read_all_tasks, write_ledger},
+};
+use anyhow::{bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// the text sealed into [`Encryption::check`].
+const CHECK: &str = "timr";
+
+/// Encryption settings, the `encryption` section of the config.
+///
+/// # Example
+/// ```json
+/// "encryption": { "key_file": "~/.config/timr/key" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// file whose contents are the key. without it, the `TIMR_PASSPHRASE` or
+    /// `TIMR_KEY_FILE` environment variables are used, or the passphrase is asked for.
+    pub key_file: Option<String>,
+}
+
+impl EncryptionConfig {
+    pub fn secret(&self) -> Option<Secret> {
+        self.key_file
+            .as_deref()
+            .map(|k| Secret::KeyFile(expand_home(k)))
+    }
+}
+
+/// What an encrypted ledger is unlocked with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Secret {
+    Passphrase(String),
+    /// a file whose contents are the key.
+    KeyFile(PathBuf),
+}
+
+impl Secret {
+    fn bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Secret::Passphrase(p) => Ok(p.as_bytes().to_vec()),
+            Secret::KeyFile(path) => std::fs::read(path)
+                .with_context(|| format!("could not read key file {}", path.display())),
+        }
+    }
+}
+
+/// How the lines of an encrypted ledger are sealed, kept in its header.
+///
+/// every line after the header is the base64 of a random nonce followed by the line
+/// sealed with XChaCha20-Poly1305, so lines can still be appended one at a time.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Encryption {
+    /// base64 salt the key is derived from the secret with, using argon2id.
+    pub salt: String,
+    /// argon2 memory in KiB, iterations, and lanes.
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    /// a known text sealed with the key, to tell a wrong key from a damaged ledger.
+    pub check: String,
+}
+
+/// A 256 bit key derived from a [`Secret`].
+#[derive(Clone)]
+pub struct Key(XChaCha20Poly1305);
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+impl Key {
+    fn derive(secret: &Secret, enc: &Encryption) -> Result<Self> {
+        let params = match Params::new(enc.m_cost, enc.t_cost, enc.p_cost, Some(32)) {
+            Ok(p) => p,
+            Err(e) => bail!("bad key derivation settings in the ledger header: {}", e),
+        };
+        let salt = STANDARD.decode(&enc.salt)?;
+        let mut key = [0u8; 32];
+        if let Err(e) = Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password_into(
+            &secret.bytes()?,
+            &salt,
+            &mut key,
+        ) {
+            bail!("could not derive the key: {}", e);
+        }
+        Ok(Self(XChaCha20Poly1305::new(&key.into())))
+    }
+
+    pub fn seal(&self, line: &str) -> String {
+        let mut nonce = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut sealed = nonce.to_vec();
+        sealed.extend(
+            self.0
+                .encrypt(XNonce::from_slice(&nonce), line.as_bytes())
+                .expect("sealing a line in memory does not fail"),
+        );
+        STANDARD.encode(sealed)
+    }
+
+    /// `None` if the line was not sealed with this key, or was changed since.
+    pub fn open(&self, line: &str) -> Option<String> {
+        let sealed = STANDARD.decode(line.trim()).ok()?;
+        if sealed.len() < 24 {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(24);
+        let plain = self.0.decrypt(XNonce::from_slice(nonce), ciphertext).ok()?;
+        String::from_utf8(plain).ok()
+    }
+}
+
+impl Encryption {
+    /// new settings with a random salt, and the key they derive from `secret`.
+    pub fn new(secret: &Secret) -> Result<(Self, Key)> {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let mut enc = Self {
+            salt: STANDARD.encode(salt),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+            check: String::new(),
+        };
+        let key = Key::derive(secret, &enc)?;
+        enc.check = key.seal(CHECK);
+        Ok((enc, key))
+    }
+}
+
+/// secrets given for each ledger, and the keys derived from them by salt.
+struct Keyring {
+    secrets: HashMap<String, Secret>,
+    keys: HashMap<(String, String), Key>,
+}
+
+static KEYRING: Mutex<Option<Keyring>> = Mutex::new(None);
+
+fn with_keyring<T>(f: impl FnOnce(&mut Keyring) -> T) -> T {
+    let mut guard = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    let ring = guard.get_or_insert_with(|| Keyring {
+        secrets: HashMap::new(),
+        keys: HashMap::new(),
+    });
+    f(ring)
+}
+
+/// sets the secret the ledger `file` and its archive are unlocked with.
+pub fn set_secret(file: &str, secret: Secret) {
+    with_keyring(|ring| {
+        ring.keys.retain(|(f, _), _| f != file);
+        ring.secrets.insert(file.to_string(), secret);
+    })
+}
+
+/// the secret for the ledger `file`: the one set with [`set_secret`], or else the one in
+/// the `TIMR_PASSPHRASE` or `TIMR_KEY_FILE` environment variables.
+pub fn secret_for(file: &str) -> Option<Secret> {
+    if let Some(s) = with_keyring(|ring| ring.secrets.get(file).cloned()) {
+        return Some(s);
+    }
+    match (
+        std::env::var("TIMR_PASSPHRASE"),
+        std::env::var("TIMR_KEY_FILE"),
+    ) {
+        (Ok(p), _) if !p.is_empty() => Some(Secret::Passphrase(p)),
+        (_, Ok(k)) if !k.is_empty() => Some(Secret::KeyFile(PathBuf::from(k))),
+        _ => None,
+    }
+}
+
+/// the key for the ledger `file`, which is encrypted with `enc`.
+///
+/// # Errors
+///
+/// returns an error if no secret was given for it, or the secret is not the one it was
+/// encrypted with.
+pub fn key_for(file: &str, enc: &Encryption) -> Result<Key> {
+    let id = (file.to_string(), enc.salt.clone());
+    if let Some(key) = with_keyring(|ring| ring.keys.get(&id).cloned()) {
+        return Ok(key);
+    }
+    let secret = match secret_for(file) {
+        Some(s) => s,
+        None => bail!(
+            "{} is encrypted. set TIMR_PASSPHRASE or TIMR_KEY_FILE, or `key_file` in the `encryption` section of the config",
+            file
+        ),
+    };
+    let key = Key::derive(&secret, enc)?;
+    if key.open(&enc.check).as_deref() != Some(CHECK) {
+        bail!("wrong passphrase or key file for {}", file);
+    }
+    with_keyring(|ring| ring.keys.insert(id, key.clone()));
+    Ok(key)
+}
+
+/// How the lines after a ledger's header are stored.
+#[derive(Debug, Clone)]
+pub enum Lines {
+    Plain,
+    Sealed(Key),
+}
+
+impl Lines {
+    /// how the lines of `file` with `header` are stored. `file` is the ledger whose
+    /// secret is used, which for an archive segment is the ledger it came from.
+    pub fn of(header: &Header, file: &str) -> Result<Self> {
+        match &header.encryption {
+            Some(enc) => Ok(Lines::Sealed(key_for(file, enc)?)),
+            None => Ok(Lines::Plain),
+        }
+    }
+
+    pub fn seal(&self, line: &str) -> String {
+        match self {
+            Lines::Plain => line.to_string(),
+            Lines::Sealed(key) => key.seal(line),
+        }
+    }
+
+    pub fn open(&self, line: &str, file: &str) -> Result<String> {
+        match self {
+            Lines::Plain => Ok(line.to_string()),
+            Lines::Sealed(key) => match key.open(line) {
+                Some(l) => Ok(l),
+                None => bail!("a line of {} could not be decrypted. it was changed, or sealed with another key", file),
+            },
+        }
+    }
+}
+
+/// encrypts the ledger `file` and its archive with `secret`, which is used for it from then on.
+/// the plain text copies `migrate` made of the ledger are removed.
+/// returns the number of tasks in the ledger, and the copies removed.
+///
+/// # Errors
+///
+/// returns an error if the ledger is already encrypted.
+pub fn encrypt(file: &str, secret: Secret) -> Result<(usize, Vec<PathBuf>)> {
+    if let Some(Header {
+        encryption: Some(_),
+        ..
+    }) = schema::header_of(file)?
+    {
+        bail!(
+            "{} is already encrypted. decrypt it first to change the key",
+            file
+        );
+    }
+    let (enc, _) = Encryption::new(&secret)?;
+    let count = convert(file, Some(enc), Some(secret))?;
+    let backups = schema::backups(file)?;
+    for b in &backups {
+        std::fs::remove_file(b)?;
+    }
+    Ok((count, backups))
+}
+
+/// decrypts the ledger `file` and its archive. returns the number of tasks in the ledger.
+///
+/// # Errors
+///
+/// returns an error if the ledger is not encrypted, or the secret for it is wrong.
+pub fn decrypt(file: &str) -> Result<usize> {
+    match schema::header_of(file)? {
+        Some(Header {
+            encryption: Some(_),
+            ..
+        }) => convert(file, None, None),
+        _ => bail!("{} is not encrypted", file),
+    }
+}
+
+/// rewrites the ledger and its archive segments with `encryption`.
+fn convert(file: &str, encryption: Option<Encryption>, secret: Option<Secret>) -> Result<usize> {
+    // everything is read with the old key before anything is written with the new one.
+    let tasks = read_all_tasks(file)?;
+    let mut segments = Vec::new();
+    for s in archive::segments(file)? {
+        let archived = s.read()?;
+        segments.push((s, archived));
+    }
+
+    if let Some(secret) = secret {
+        set_secret(file, secret);
+    }
+    let header = Header {
+        encryption,
+        ..Header::current()
+    };
+    write_ledger(&tasks, &header, file)?;
+    for (s, archived) in segments {
+        s.write(&archived)?;
+    }
+    Ok(tasks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{archive::ArchiveConfig, tasks::Task, testing::temp_dir, utility::*};
+    use crate::Timr;
+
+    #[test]
+    fn test_seal_and_open() {
+        let file = "timr_encryption_test.json";
+        set_secret(file, Secret::Passphrase("correct horse".to_string()));
+        let (enc, key) = Encryption::new(&secret_for(file).unwrap()).unwrap();
+
+        let sealed = key.seal("client meeting");
+        assert!(!sealed.contains("client"));
+        assert_ne!(sealed, key.seal("client meeting"));
+        assert_eq!(key.open(&sealed).as_deref(), Some("client meeting"));
+        assert!(key.open("bm90IHNlYWxlZA==").is_none());
+
+        assert!(key_for(file, &enc).is_ok());
+        set_secret(file, Secret::Passphrase("wrong horse".to_string()));
+        let err = key_for(file, &enc).unwrap_err().to_string();
+        assert!(err.contains("wrong passphrase"), "{}", err);
+    }
+
+    #[test]
+    fn test_encrypt_ledger() {
+        let dir = temp_dir("encrypt");
+        let timr = Timr::new(dir.join("timr.json").to_string_lossy());
+        let file = timr.file();
+
+        let old = Task::new(
+            "2023-10-6".into(),
+            "acme ticket".into(),
+            "0900".into(),
+            Some("1000".into()),
+            60,
+        );
+        output_task_to_file(old, file).unwrap();
+        archive::archive(&timr, timr.today(), &ArchiveConfig::default()).unwrap();
+        timr.start("client call", None).unwrap();
+
+        // a copy left by an earlier migration.
+        let backup = dir.join("timr.json.v0.bak");
+        std::fs::write(&backup, "acme ticket").unwrap();
+
+        let key_file = dir.join("key");
+        std::fs::write(&key_file, b"not a passphrase").unwrap();
+        let (count, removed) = encrypt(file, Secret::KeyFile(key_file.clone())).unwrap();
+        assert_eq!((count, removed), (1, vec![backup.clone()]));
+        assert!(!backup.exists());
+        assert!(encrypt(file, Secret::KeyFile(key_file.clone())).is_err());
+
+        // nothing is left in plain text, and every path still works.
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let contents = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!contents.contains("acme") && !contents.contains("client"));
+        }
+        timr.stop("client call", None).unwrap();
+        timr.compact().unwrap();
+        let all = read_tasks_between(None, timr.today(), file).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let guess = timr
+            .clone()
+            .with_secret(Secret::Passphrase("guess".to_string()));
+        let err = guess
+            .list(crate::ListFilter::Running)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("wrong passphrase or key file"), "{}", err);
+
+        let timr = timr.clone().with_secret(Secret::KeyFile(key_file));
+        assert!(timr.list(crate::ListFilter::Running).is_ok());
+        assert_eq!(decrypt(file).unwrap(), 1);
+        assert!(std::fs::read_to_string(file)
+            .unwrap()
+            .contains("client call"));
+        assert_eq!(read_tasks_between(None, timr.today(), file).unwrap(), all);
+        assert!(decrypt(file).is_err());
+    }
+}
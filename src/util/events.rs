@@ -0,0 +1,279 @@
+use super::{
+    encryption::Lines,
+    schema::{self, Header},
+    tasks::Task,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+
+/// What a task is found by in the ledger: its id, or for tasks from before ids,
+/// its name, the day it started, and its start time.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskKey {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub task_name: String,
+    pub date: String,
+    pub time_start: String,
+}
+
+impl TaskKey {
+    pub fn of(t: &Task) -> Self {
+        Self {
+            id: t.id.clone(),
+            task_name: t.task_name.clone(),
+            date: t.date.clone(),
+            time_start: t.time_start.clone(),
+        }
+    }
+
+    pub fn matches(&self, t: &Task) -> bool {
+        match (&self.id, &t.id) {
+            (Some(id), Some(other)) => id == other,
+            _ => {
+                t.task_name == self.task_name
+                    && t.date == self.date
+                    && t.time_start == self.time_start
+            }
+        }
+    }
+}
+
+/// A change to the ledger, appended to the end of the file.
+///
+/// the ledger is a snapshot of tasks, newest first, followed by the events since the
+/// snapshot was written. reading it replays the events onto the snapshot, and
+/// [`compact`](super::utility::compact) writes the result back as a new snapshot.
+///
+/// # Example
+/// ```json
+/// {"event":"start","task":{"date":"2023-10-6","task_name":"debugging","time_start":"0900","time_end":null,"time_total":0}}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event {
+    /// a task was started, or logged after it ended.
+    Start { task: Task },
+    /// a running task was ended. it is found by its own key.
+    End { task: Task },
+    /// a task was changed, maybe including its key. `old` is the key it had before.
+    Amend { old: TaskKey, task: Task },
+    /// a task was deleted. `old` is its key.
+    Delete { old: TaskKey },
+}
+
+/// A line of the ledger.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Header(Header),
+    Event(Event),
+    Task(Task),
+}
+
+impl Event {
+    /// brings the tasks in the event up to the current version of the ledger.
+    fn upgrade(self, version: u32) -> Self {
+        match self {
+            Event::Start { task } => Event::Start {
+                task: schema::upgrade(task, version),
+            },
+            Event::End { task } => Event::End {
+                task: schema::upgrade(task, version),
+            },
+            Event::Amend { old, task } => Event::Amend {
+                old: schema::upgrade_key(old, version),
+                task: schema::upgrade(task, version),
+            },
+            Event::Delete { old } => Event::Delete {
+                old: schema::upgrade_key(old, version),
+            },
+        }
+    }
+
+    /// applies the event to `tasks`, which are newest first.
+    /// a change to a task that is not there adds it, so that nothing written is lost.
+    pub fn apply(self, tasks: &mut Vec<Task>) {
+        let (key, task) = match self {
+            Event::Start { task } => return tasks.insert(0, task),
+            Event::End { task } => (TaskKey::of(&task), task),
+            Event::Amend { old, task } => (old, task),
+            Event::Delete { old } => {
+                if let Some(i) = tasks.iter().position(|t| old.matches(t)) {
+                    tasks.remove(i);
+                }
+                return;
+            }
+        };
+        match tasks.iter().position(|t| key.matches(t)) {
+            Some(i) => tasks[i] = task,
+            None => tasks.insert(0, task),
+        }
+    }
+}
+
+/// the tasks in the ledger `contents`, with its events replayed. newest first.
+/// tasks from older versions of the ledger are upgraded as they are read, and the lines
+/// of an encrypted one are decrypted with the secret for `file`.
+pub fn replay(contents: &str, file: &str) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+    // a ledger without a header is from before the format was versioned.
+    let mut version = 0;
+    let mut lines = Lines::Plain;
+    for l in contents.lines().filter(|l| !l.trim().is_empty()) {
+        let l = lines.open(l, file)?;
+        match serde_json::from_str(&l) {
+            Ok(Line::Header(h)) => {
+                h.check()?;
+                version = h.timr_version;
+                lines = Lines::of(&h, file)?;
+            }
+            Ok(Line::Event(e)) => e.upgrade(version).apply(&mut tasks),
+            Ok(Line::Task(t)) => tasks.push(schema::upgrade(t, version)),
+            Err(e) => anyhow::bail!("error parsing ledger line {}: {}", l, e),
+        }
+    }
+    Ok(tasks)
+}
+
+/// appends `events` to the ledger `file` in a single write, encrypted if the ledger is.
+/// a new file starts with a header. lines end the way the file's first line does.
+pub fn append(events: &[Event], file: &str) -> Result<()> {
+    let exists = std::path::Path::new(file).exists();
+    let (mut buf, lines) = match (exists, schema::header_of(file)?) {
+        (false, _) => (Header::current().to_json_string(), Lines::Plain),
+        (true, Some(h)) => (String::new(), Lines::of(&h, file)?),
+        (true, None) => (String::new(), Lines::Plain),
+    };
+    let newline = match exists {
+        true => line_ending(file)?,
+        false => "\n",
+    };
+    for e in events {
+        buf += &lines.seal(&serde_json::to_string(e)?);
+        buf += newline;
+    }
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    Ok(f.write_all(buf.as_bytes())?)
+}
+
+/// the line ending used in `file`: `\r\n` if its first line ends with one, otherwise `\n`.
+fn line_ending(file: &str) -> Result<&'static str> {
+    let mut first = Vec::new();
+    BufReader::new(std::fs::File::open(file)?).read_until(b'\n', &mut first)?;
+    Ok(match first.ends_with(b"\r\n") {
+        true => "\r\n",
+        false => "\n",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, start: &str, end: Option<&str>) -> Task {
+        Task::new(
+            "2023-10-6".to_string(),
+            name.to_string(),
+            start.to_string(),
+            end.map(String::from),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_replay() {
+        let snapshot = format!(
+            "{}\r\n{}\r\n",
+            serde_json::to_string(&task("review", "0800", Some("0830"))).unwrap(),
+            serde_json::to_string(&task("email", "0700", Some("0715"))).unwrap()
+        );
+        let events = [
+            Event::Start {
+                task: task("debugging", "0900", None),
+            },
+            Event::End {
+                task: task("debugging", "0900", Some("1000")),
+            },
+            Event::Amend {
+                old: TaskKey::of(&task("email", "0700", Some("0715"))),
+                task: task("email", "0705", Some("0715")),
+            },
+            Event::Delete {
+                old: TaskKey::of(&task("review", "0800", Some("0830"))),
+            },
+        ];
+        let mut contents = Header::current().to_json_string() + &snapshot;
+        for e in &events {
+            contents += &format!("{}\n", serde_json::to_string(e).unwrap());
+        }
+        assert!(contents.contains(r#"{"event":"end","task":{"#));
+
+        let tasks = replay(&contents, "timr.json").unwrap();
+        assert_eq!(
+            tasks,
+            vec![
+                task("debugging", "0900", Some("1000")),
+                task("email", "0705", Some("0715")),
+            ]
+        );
+        assert!(replay("not json", "timr.json").is_err());
+    }
+
+    #[test]
+    fn test_append_keeps_line_endings() {
+        let file = crate::util::testing::temp_file("events_crlf");
+        let legacy = serde_json::to_string(&task("review", "0800", Some("0830"))).unwrap();
+        std::fs::write(&file, format!("{}\r\n", legacy)).unwrap();
+        append(
+            &[Event::Start {
+                task: task("debugging", "0900", None),
+            }],
+            &file,
+        )
+        .unwrap();
+
+        let contents = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(contents.matches("\r\n").count(), 2);
+        assert_eq!(contents.matches('\n').count(), 2);
+        assert_eq!(replay(&contents, &file).unwrap().len(), 2);
+        _ = std::fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_apply_by_id() {
+        // two tasks started in the same minute, told apart by their ids.
+        let with_id = |id: &str, end: Option<&str>| Task {
+            id: Some(id.to_string()),
+            ..task("debugging", "0900", end)
+        };
+        let mut tasks = vec![with_id("b", None), with_id("a", None)];
+        Event::End {
+            task: with_id("a", Some("0930")),
+        }
+        .apply(&mut tasks);
+        assert_eq!(tasks, vec![with_id("b", None), with_id("a", Some("0930"))]);
+
+        Event::Delete {
+            old: TaskKey::of(&with_id("b", None)),
+        }
+        .apply(&mut tasks);
+        assert_eq!(tasks, vec![with_id("a", Some("0930"))]);
+
+        // a task that was given an id after it was written is still found by its key.
+        let mut legacy = vec![task("email", "0700", None)];
+        Event::End {
+            task: Task {
+                id: Some("c".to_string()),
+                ..task("email", "0700", Some("0715"))
+            },
+        }
+        .apply(&mut legacy);
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].time_end.as_deref(), Some("0715"));
+    }
+}
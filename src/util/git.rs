@@ -0,0 +1,173 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How the branch is used when a task is started without a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchAs {
+    /// the branch is the task name.
+    #[default]
+    Name,
+    /// the repository is the task name and the branch is a tag.
+    Tag,
+}
+
+/// Git settings, the `git` section of the config.
+///
+/// # Example
+/// ```json
+/// "git": { "enabled": true, "branch_as": "tag" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// record the branch and repository of tasks started inside a git repository.
+    pub enabled: bool,
+    pub branch_as: BranchAs,
+}
+
+/// A git repository on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    /// the checked out files.
+    pub work_tree: PathBuf,
+    /// the `.git` directory, which is elsewhere for linked worktrees.
+    pub git_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub time: DateTime<FixedOffset>,
+    pub author: String,
+    pub summary: String,
+}
+
+impl Repo {
+    /// finds the repository `dir` is in, looking in `dir` and then each of its parents.
+    pub fn discover(dir: &Path) -> Option<Repo> {
+        for d in dir.ancestors() {
+            let dot_git = d.join(".git");
+            if dot_git.is_dir() {
+                return Some(Repo {
+                    work_tree: d.to_path_buf(),
+                    git_dir: dot_git,
+                });
+            }
+            // linked worktrees have a `.git` file pointing at their git directory.
+            if let Ok(s) = std::fs::read_to_string(&dot_git) {
+                if let Some(git_dir) = s.trim().strip_prefix("gitdir:") {
+                    return Some(Repo {
+                        work_tree: d.to_path_buf(),
+                        git_dir: d.join(git_dir.trim()),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// the name of the repository, taken from the directory it is in.
+    pub fn name(&self) -> String {
+        match self.work_tree.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => self.work_tree.to_string_lossy().to_string(),
+        }
+    }
+
+    /// the checked out branch, read from `HEAD`. `None` if no branch is checked out.
+    pub fn branch(&self) -> Result<Option<String>> {
+        let head = std::fs::read_to_string(self.git_dir.join("HEAD"))
+            .with_context(|| format!("could not read HEAD in {}", self.git_dir.display()))?;
+        Ok(head
+            .trim()
+            .strip_prefix("ref: refs/heads/")
+            .map(|b| b.to_string()))
+    }
+
+    /// the commits on any branch made between `from` and `to`, newest first.
+    /// runs `git log`, so git has to be installed.
+    pub fn commits_between(
+        &self,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+    ) -> Result<Vec<Commit>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .args(["log", "--all", "--format=%H%x09%cI%x09%an%x09%s"])
+            .arg(format!("--since={}", from.to_rfc3339()))
+            .arg(format!("--until={}", to.to_rfc3339()))
+            .output()
+            .context("could not run git")?;
+        if !output.status.success() {
+            bail!(
+                "git log failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let commits = parse_log(&String::from_utf8_lossy(&output.stdout))?;
+        Ok(commits
+            .into_iter()
+            .filter(|c| c.time >= from && c.time <= to)
+            .collect())
+    }
+}
+
+/// parses `git log` output with one tab separated `hash, date, author, subject` per line.
+fn parse_log(s: &str) -> Result<Vec<Commit>> {
+    let mut commits = Vec::new();
+    for line in s.lines().filter(|l| !l.is_empty()) {
+        let fields: Vec<&str> = line.splitn(4, '\t').collect();
+        let [hash, time, author, summary] = fields.as_slice() else {
+            bail!("could not understand git log line {}", line);
+        };
+        commits.push(Commit {
+            hash: hash.to_string(),
+            time: DateTime::parse_from_rfc3339(time)?,
+            author: author.to_string(),
+            summary: summary.to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_and_branch() {
+        let root = crate::util::testing::temp_dir("git");
+        let nested = root.join("src").join("util");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(
+            root.join(".git").join("HEAD"),
+            "ref: refs/heads/feature/login\n",
+        )
+        .unwrap();
+
+        let repo = Repo::discover(&nested).unwrap();
+        assert_eq!(repo.work_tree, root);
+        assert_eq!(repo.branch().unwrap().as_deref(), Some("feature/login"));
+
+        std::fs::write(root.join(".git").join("HEAD"), "3f9a1c0d\n").unwrap();
+        assert_eq!(repo.branch().unwrap(), None);
+
+        _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let log = "a1b2c3\t2023-10-20T09:15:00+02:00\tAda\tfix the\ttabs\n\n";
+        let commits = parse_log(log).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "fix the\ttabs");
+        assert_eq!(commits[0].time.to_rfc3339(), "2023-10-20T09:15:00+02:00");
+        assert!(parse_log("a1b2c3 no tabs").is_err());
+    }
+}
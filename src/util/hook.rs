@@ -0,0 +1,243 @@
+use super::{service::Timr, tasks::Task, utility::*};
+use anyhow::Result;
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// tag given to every task started by the shell hook.
+pub const HOOK_TAG: &str = "hook";
+
+/// A project directory and the task to track while working in it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Directory {
+    /// the directory, which also covers everything below it. `~` is the home directory.
+    pub path: String,
+    pub task: String,
+    pub tags: Vec<String>,
+}
+
+/// Shell hook settings, the `hook` section of the config.
+///
+/// # Example
+/// ```json
+/// "hook": { "directories": [{ "path": "~/src/timr", "task": "timr" }], "idle_minutes": 20 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HookConfig {
+    pub directories: Vec<Directory>,
+    /// a shell that has not shown a prompt for this long is idle,
+    /// and the task is ended at the last prompt.
+    pub idle_minutes: i64,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            directories: Vec::new(),
+            idle_minutes: 15,
+        }
+    }
+}
+
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl HookConfig {
+    /// the configured directory `dir` is in. the deepest one wins when they are nested.
+    pub fn directory_for(&self, dir: &Path) -> Option<&Directory> {
+        self.directories
+            .iter()
+            .map(|d| (d, expand_home(&d.path)))
+            .filter(|(_, path)| dir.starts_with(path))
+            .max_by_key(|(_, path)| path.components().count())
+            .map(|(d, _)| d)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// quotes `s` for `shell`.
+fn quote(shell: Shell, s: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", s.replace('\'', "'\\''")),
+        Shell::Fish => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+    }
+}
+
+/// the snippet to add to the shell's startup file. each time a prompt is shown it runs `exe`
+/// with the ledger and config in `home`.
+pub fn snippet(shell: Shell, exe: &Path, home: &Path) -> String {
+    let exe = quote(shell, &exe.to_string_lossy());
+    let home = quote(shell, &home.to_string_lossy());
+    let run = format!("{exe} hook-prompt --home {home}");
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            let install = match shell {
+                Shell::Bash => "PROMPT_COMMAND=\"__timr_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"",
+                _ => "autoload -Uz add-zsh-hook\nadd-zsh-hook precmd __timr_hook",
+            };
+            format!(
+                "__timr_hook() {{\n    \
+                 local now=\"$(date +%s)\"\n    \
+                 {run} \"$PWD\" --last-prompt \"${{__timr_last:-$now}}\" >/dev/null 2>&1\n    \
+                 __timr_last=\"$now\"\n\
+                 }}\n{install}\n"
+            )
+        }
+        Shell::Fish => format!(
+            "function __timr_hook --on-event fish_prompt\n    \
+             set -l now (date +%s)\n    \
+             set -q __timr_last; or set -g __timr_last $now\n    \
+             {run} $PWD --last-prompt $__timr_last >/dev/null 2>&1\n    \
+             set -g __timr_last $now\n\
+             end\n"
+        ),
+    }
+}
+
+/// What the hook did on a prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Prompted {
+    /// tasks ended, either because the shell was idle or because it moved to another project.
+    pub ended: Vec<Task>,
+    pub started: Option<Task>,
+}
+
+/// runs when a prompt is shown in `dir`. `last_prompt` is when the shell showed its previous one.
+///
+/// if the shell has been idle, running hook tasks are ended at `last_prompt`. then, if `dir` is
+/// in a configured directory, its task is started, ending any other hook task. leaving the
+/// configured directories leaves the task running until the shell is idle.
+pub fn on_prompt(
+    timr: &Timr,
+    dir: &Path,
+    last_prompt: Option<DateTime<FixedOffset>>,
+) -> Result<Prompted> {
+    let config = &timr.config().hook;
+    let mut prompted = Prompted::default();
+    let mut running: Vec<Task> = read_incomplete_tasks(timr.file())?
+        .into_iter()
+        .filter(|t| t.has_tag(HOOK_TAG))
+        .collect();
+
+    if let Some(last) = last_prompt {
+        if timr.now() - last >= Duration::minutes(config.idle_minutes) {
+            for t in running.drain(..) {
+                // a task started after the last prompt, by another shell, ends where it began.
+                let end = t.started_at.map_or(last, |started| started.max(last));
+                prompted.ended.push(timr.stop_at(&t.task_name, end)?);
+            }
+        }
+    }
+
+    let d = match config.directory_for(dir) {
+        Some(d) => d,
+        None => return Ok(prompted),
+    };
+    if running.iter().any(|t| t.task_name == d.task) {
+        return Ok(prompted);
+    }
+    for t in running {
+        prompted.ended.push(timr.stop(&t.task_name, None)?);
+    }
+    let mut tags = d.tags.clone();
+    tags.push(HOOK_TAG.to_string());
+    prompted.started = Some(timr.start_with_tags(&d.task, None, &tags)?);
+    Ok(prompted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::config::Config;
+    use crate::util::testing::temp_file;
+
+    fn temp_timr(name: &str) -> Timr {
+        let mut config = Config::default();
+        config.hook.directories = vec![
+            Directory {
+                path: "/src/timr".to_string(),
+                task: "timr".to_string(),
+                ..Default::default()
+            },
+            Directory {
+                path: "/src/timr/docs".to_string(),
+                task: "docs".to_string(),
+                tags: vec!["writing".to_string()],
+            },
+        ];
+        Timr::new(temp_file(name)).with_config(config)
+    }
+
+    #[test]
+    fn test_directory_for() {
+        let config = temp_timr("hook_dirs").config().hook.clone();
+        let task = |dir: &str| {
+            config
+                .directory_for(Path::new(dir))
+                .map(|d| d.task.as_str())
+        };
+        assert_eq!(task("/src/timr"), Some("timr"));
+        assert_eq!(task("/src/timr/src/util"), Some("timr"));
+        assert_eq!(task("/src/timr/docs/api"), Some("docs"));
+        assert_eq!(task("/src/timrs"), None);
+        assert_eq!(task("/tmp"), None);
+    }
+
+    #[test]
+    fn test_on_prompt() {
+        let timr = temp_timr("hook_prompt");
+        let p = on_prompt(&timr, Path::new("/src/timr/src"), None).unwrap();
+        assert_eq!(p.started.unwrap().task_name, "timr");
+
+        // still in the same project, nothing changes.
+        let p = on_prompt(&timr, Path::new("/src/timr"), Some(timr.now())).unwrap();
+        assert_eq!(p, Prompted::default());
+
+        // leaving the project keeps the task running.
+        let p = on_prompt(&timr, Path::new("/tmp"), Some(timr.now())).unwrap();
+        assert_eq!(p, Prompted::default());
+
+        let p = on_prompt(&timr, Path::new("/src/timr/docs"), Some(timr.now())).unwrap();
+        assert_eq!(p.ended[0].task_name, "timr");
+        let docs = p.started.unwrap();
+        assert!(docs.has_tag("writing") && docs.has_tag(HOOK_TAG));
+
+        // an idle shell ends the task at the last prompt.
+        let last = timr.now() - Duration::minutes(30);
+        let p = on_prompt(&timr, Path::new("/tmp"), Some(last)).unwrap();
+        assert_eq!(p.ended[0].task_name, "docs");
+        assert_eq!(p.ended[0].ended_at, docs.started_at);
+        assert!(p.started.is_none());
+
+        // tasks started by hand are left alone.
+        timr.start("email", None).unwrap();
+        on_prompt(&timr, Path::new("/src/timr"), Some(last)).unwrap();
+        assert!(timr.is_running("email").unwrap());
+    }
+
+    #[test]
+    fn test_snippet() {
+        let bash = snippet(
+            Shell::Bash,
+            Path::new("/usr/bin/timr"),
+            Path::new("/home/ada/it's"),
+        );
+        assert!(bash.contains("'/usr/bin/timr' hook-prompt --home '/home/ada/it'\\''s' \"$PWD\""));
+        assert!(bash.contains("PROMPT_COMMAND="));
+        assert!(snippet(Shell::Zsh, Path::new("timr"), Path::new("/")).contains("add-zsh-hook"));
+        let fish = snippet(Shell::Fish, Path::new("timr"), Path::new("/home/ada/it's"));
+        assert!(fish.contains("--home '/home/ada/it\\'s' $PWD"));
+    }
+}
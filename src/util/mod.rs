@@ -0,0 +1,23 @@
+pub mod archive;
+pub mod billing;
+pub mod calc;
+pub mod config;
+pub mod dates;
+pub mod doctor;
+pub mod duration;
+pub mod encryption;
+pub mod events;
+pub mod git;
+pub mod hook;
+pub mod pomodoro;
+pub mod rounding;
+pub mod schema;
+pub mod server;
+pub mod service;
+pub mod sync;
+pub mod targets;
+pub mod tasks;
+pub mod templates;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod utility;
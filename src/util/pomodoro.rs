@@ -0,0 +1,224 @@
+use super::{service::Timr, tasks::Task, utility::*};
+use anyhow::{bail, Result};
+use chrono::Duration;
+use std::process::Command;
+
+/// tag given to every work interval logged by a pomodoro session.
+pub const POMODORO_TAG: &str = "pomodoro";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl std::fmt::Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Work => write!(f, "work"),
+            Phase::ShortBreak => write!(f, "short break"),
+            Phase::LongBreak => write!(f, "long break"),
+        }
+    }
+}
+
+/// Settings for a pomodoro session. all lengths are in minutes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pomodoro {
+    /// name of the task each work interval is logged as.
+    pub task_name: String,
+    pub work: i64,
+    pub short_break: i64,
+    pub long_break: i64,
+    /// a long break is taken after this many work intervals.
+    pub long_break_every: u32,
+    /// number of work intervals in the session.
+    pub rounds: u32,
+    /// shell command to run when a phase ends. the terminal bell is used if this is `None`.
+    pub notify: Option<String>,
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Self {
+            task_name: POMODORO_TAG.to_string(),
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            long_break_every: 4,
+            rounds: 4,
+            notify: None,
+        }
+    }
+}
+
+impl Pomodoro {
+    /// the phases of the session and their lengths. the session ends on the last work interval,
+    /// so no break follows it.
+    pub fn phases(&self) -> Vec<(Phase, i64)> {
+        let mut phases = Vec::new();
+        for round in 1..=self.rounds {
+            phases.push((Phase::Work, self.work));
+            if round == self.rounds {
+                break;
+            }
+            match self.long_break_every > 0 && round % self.long_break_every == 0 {
+                true => phases.push((Phase::LongBreak, self.long_break)),
+                false => phases.push((Phase::ShortBreak, self.short_break)),
+            }
+        }
+        phases
+    }
+
+    /// the work interval left running by an interrupted session, if any.
+    fn running_interval(&self, timr: &Timr) -> Result<Option<Task>> {
+        Ok(read_incomplete_tasks(timr.file())?
+            .into_iter()
+            .find(|t| t.task_name == self.task_name && t.has_tag(POMODORO_TAG)))
+    }
+
+    /// picks up a work interval left running by an interrupted session.
+    ///
+    /// returns the minutes left on it, or `None` if there is nothing to resume.
+    /// intervals that should have already finished are ended at the time they were due.
+    pub fn resume(&self, timr: &Timr) -> Result<Option<i64>> {
+        let t = match self.running_interval(timr)? {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let (start, _) = timr.span(&t)?;
+        let elapsed = (timr.now() - start).num_minutes();
+        if (0..self.work).contains(&elapsed) {
+            return Ok(Some(self.work - elapsed));
+        }
+        self.finish_work(timr)?;
+        Ok(None)
+    }
+
+    /// logs the start of a work interval.
+    pub fn start_work(&self, timr: &Timr) -> Result<Task> {
+        timr.start_with_tags(&self.task_name, None, &[POMODORO_TAG.to_string()])
+    }
+
+    /// ends the running work interval exactly one work length after it started.
+    pub fn finish_work(&self, timr: &Timr) -> Result<Task> {
+        let t = match self.running_interval(timr)? {
+            Some(t) => t,
+            None => bail!("there is no running {} interval", self.task_name),
+        };
+        let (start, _) = timr.span(&t)?;
+        timr.stop_at(&self.task_name, start + Duration::minutes(self.work))
+    }
+
+    /// tells the user a phase is over, with the notify command if there is one,
+    /// otherwise with the terminal bell. the message is passed to the command as `TIMR_MESSAGE`.
+    pub fn notify(&self, message: &str) -> Result<()> {
+        let cmd = match &self.notify {
+            Some(cmd) => cmd,
+            None => {
+                print!("\x07");
+                return Ok(());
+            }
+        };
+        let mut shell = match cfg!(windows) {
+            true => {
+                let mut c = Command::new("cmd");
+                c.arg("/C");
+                c
+            }
+            false => {
+                let mut c = Command::new("sh");
+                c.arg("-c");
+                c
+            }
+        };
+        let status = shell.arg(cmd).env("TIMR_MESSAGE", message).status()?;
+        if !status.success() {
+            bail!("notify command `{}` failed: {}", cmd, status);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::testing::temp_file;
+
+    #[test]
+    fn test_phases() {
+        let p = Pomodoro {
+            rounds: 5,
+            long_break_every: 2,
+            ..Default::default()
+        };
+        let phases: Vec<Phase> = p.phases().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            phases,
+            vec![
+                Phase::Work,
+                Phase::ShortBreak,
+                Phase::Work,
+                Phase::LongBreak,
+                Phase::Work,
+                Phase::ShortBreak,
+                Phase::Work,
+                Phase::LongBreak,
+                Phase::Work,
+            ]
+        );
+        assert_eq!(p.phases()[0], (Phase::Work, 25));
+    }
+
+    #[test]
+    fn test_resume_closes_stale_interval() {
+        let timr = Timr::new(temp_file("pomodoro"));
+
+        let mut stale = Task::new(
+            "2023-10-6".to_string(),
+            POMODORO_TAG.to_string(),
+            "0900".to_string(),
+            None,
+            0,
+        );
+        stale.tags = vec![POMODORO_TAG.to_string()];
+        output_task_to_file(stale, timr.file()).unwrap();
+
+        let p = Pomodoro::default();
+        assert_eq!(p.resume(&timr).unwrap(), None);
+
+        let tasks = read_all_tasks(timr.file()).unwrap();
+        assert_eq!(tasks[0].time_end, Some("0925".to_string()));
+        assert_eq!(tasks[0].time_total, 25);
+        assert!(p.resume(&timr).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_finish_work_past_midnight() {
+        let timr = Timr::new(temp_file("pomodoro_midnight"));
+        let started = chrono::DateTime::parse_from_rfc3339("2023-10-06T23:50:00+02:00").unwrap();
+        let mut late = Task::new(
+            "2023-10-6".to_string(),
+            POMODORO_TAG.to_string(),
+            "2350".to_string(),
+            None,
+            0,
+        );
+        late.started_at = Some(started);
+        late.tags = vec![POMODORO_TAG.to_string()];
+        output_task_to_file(late, timr.file()).unwrap();
+
+        let p = Pomodoro::default();
+        let t = p.finish_work(&timr).unwrap();
+        assert_eq!(t.ended_at, Some(started + Duration::minutes(25)));
+        assert_eq!(t.time_end.as_deref(), Some("0015"));
+        assert_eq!(t.time_total, 25);
+
+        // an interval started just now is resumed with the rest of its time.
+        let mut now = p.start_work(&timr).unwrap();
+        now.started_at = Some(timr.now() - Duration::minutes(10));
+        update_task_in_file(now, timr.file()).unwrap();
+        assert_eq!(p.resume(&timr).unwrap(), Some(15));
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+/// Which way to round a number of minutes that falls between two increments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    /// to the closest increment, halfway rounds up.
+    #[default]
+    Nearest,
+    Up,
+    Down,
+}
+
+/// Rounds minutes to a fixed increment, e.g. 6 minutes (0.1h) or 15 minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rounding {
+    /// size of the increment in minutes. 1 (or less) leaves minutes as they are.
+    pub increment: i64,
+    pub mode: RoundingMode,
+}
+
+impl Default for Rounding {
+    fn default() -> Self {
+        Self {
+            increment: 1,
+            mode: RoundingMode::Nearest,
+        }
+    }
+}
+
+impl Rounding {
+    pub fn new(increment: i64, mode: RoundingMode) -> Self {
+        Self { increment, mode }
+    }
+
+    /// rounds `minutes` to the increment.
+    /// # Examples
+    /// * 15 minute increments, nearest: 22 is 15, 23 is 30
+    pub fn apply(&self, minutes: i64) -> i64 {
+        let inc = self.increment;
+        if inc <= 1 {
+            return minutes;
+        }
+        let down = minutes.div_euclid(inc) * inc;
+        let rem = minutes - down;
+        match self.mode {
+            _ if rem == 0 => minutes,
+            RoundingMode::Down => down,
+            RoundingMode::Up => down + inc,
+            RoundingMode::Nearest if rem * 2 >= inc => down + inc,
+            RoundingMode::Nearest => down,
+        }
+    }
+}
+
+/// Whether timecard rounding applies to each entry or to each day's total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingScope {
+    #[default]
+    Entry,
+    Day,
+}
+
+/// Rounding for timecards, the `rounding` section of the config.
+/// the unrounded minutes are always kept in the ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TimecardRounding {
+    #[serde(flatten)]
+    pub rounding: Rounding,
+    pub per: RoundingScope,
+}
+
+impl TimecardRounding {
+    /// rounds a single entry. entries are left alone when rounding per day.
+    pub fn entry(&self, minutes: i64) -> i64 {
+        match self.per {
+            RoundingScope::Entry => self.rounding.apply(minutes),
+            RoundingScope::Day => minutes,
+        }
+    }
+
+    /// rounds a day's total, given the raw minutes and the sum of its (already rounded) entries.
+    pub fn day(&self, minutes: i64, entries: i64) -> i64 {
+        match self.per {
+            RoundingScope::Entry => entries,
+            RoundingScope::Day => self.rounding.apply(minutes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let nearest = Rounding::new(15, RoundingMode::Nearest);
+        assert_eq!(nearest.apply(22), 15);
+        assert_eq!(nearest.apply(23), 30);
+        assert_eq!(nearest.apply(30), 30);
+
+        let up = Rounding::new(6, RoundingMode::Up);
+        assert_eq!(up.apply(1), 6);
+        assert_eq!(up.apply(60), 60);
+
+        let down = Rounding::new(6, RoundingMode::Down);
+        assert_eq!(down.apply(65), 60);
+
+        assert_eq!(Rounding::default().apply(37), 37);
+    }
+
+    #[test]
+    fn test_timecard_rounding() {
+        let per_entry = TimecardRounding {
+            rounding: Rounding::new(15, RoundingMode::Up),
+            per: RoundingScope::Entry,
+        };
+        assert_eq!(per_entry.entry(20), 30);
+        assert_eq!(per_entry.day(40, 60), 60);
+
+        let per_day = TimecardRounding {
+            per: RoundingScope::Day,
+            ..per_entry
+        };
+        assert_eq!(per_day.entry(20), 20);
+        assert_eq!(per_day.day(40, 40), 45);
+
+        let json = r#"{"increment": 6, "mode": "down", "per": "day"}"#;
+        let parsed: TimecardRounding = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.rounding, Rounding::new(6, RoundingMode::Down));
+        assert_eq!(parsed.per, RoundingScope::Day);
+    }
+}
@@ -0,0 +1,256 @@
+use super::{encryption::Encryption, events::TaskKey, tasks::Task, utility::*};
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// The version of the ledger format this build writes.
+///
+/// * 0: no header. dates may be zero padded, times may be `%H:%M:%S` or miss their
+///   leading zero, and `time_total` may not match the start and end.
+/// * 1: starts with a [`Header`]. dates are unpadded, times are `%H%M`, and totals are
+///   worked out from the times.
+pub const VERSION: u32 = 1;
+
+/// The first line of a versioned ledger, e.g. `{"timr_version":1}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Header {
+    pub timr_version: u32,
+    /// how the lines after the header are encrypted, if they are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<Encryption>,
+}
+
+impl Header {
+    pub fn current() -> Self {
+        Self {
+            timr_version: VERSION,
+            encryption: None,
+        }
+    }
+
+    /// errors if the ledger was written by a newer timr, which this one cannot read.
+    pub fn check(&self) -> Result<()> {
+        if self.timr_version > VERSION {
+            bail!(
+                "the ledger is version {}, but this timr only reads up to version {}. update timr to read it.",
+                self.timr_version,
+                VERSION
+            );
+        }
+        Ok(())
+    }
+
+    pub fn to_json_string(&self) -> String {
+        format!("{}\n", serde_json::to_string(self).unwrap())
+    }
+}
+
+/// the header of the ledger `file`. `None` if it does not exist, or is from before
+/// the format was versioned.
+pub fn header_of(file: &str) -> Result<Option<Header>> {
+    if !Path::new(file).exists() {
+        return Ok(None);
+    }
+    let mut first = String::new();
+    std::io::BufReader::new(std::fs::File::open(file)?).read_line(&mut first)?;
+    Ok(serde_json::from_str(first.trim()).ok())
+}
+
+/// the version of the ledger `file`, from its header. a ledger with no header is version 0,
+/// and one that does not exist yet will be written in the current version.
+pub fn version_of(file: &str) -> Result<u32> {
+    Ok(match (Path::new(file).exists(), header_of(file)?) {
+        (false, _) => VERSION,
+        (true, Some(h)) => h.timr_version,
+        (true, None) => 0,
+    })
+}
+
+fn upgrade_date(date: &str) -> String {
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(d) => format_date(d),
+        Err(_) => date.to_string(),
+    }
+}
+
+fn upgrade_time(time: &str) -> String {
+    match parse_time(time) {
+        Ok(t) => t.format("%H%M").to_string(),
+        Err(_) => time.to_string(),
+    }
+}
+
+/// brings a task read from a ledger of `version` up to the current version.
+pub fn upgrade(mut task: Task, version: u32) -> Task {
+    if version >= VERSION {
+        return task;
+    }
+    task.date = upgrade_date(&task.date);
+    task.time_start = upgrade_time(&task.time_start);
+    task.time_end = task.time_end.as_deref().map(upgrade_time);
+    task.time_total = match (task.minutes(), &task.time_end) {
+        (Some(m), _) => m,
+        (None, Some(end)) => match (parse_time(&task.time_start), parse_time(end)) {
+            // an end before the start ran past midnight.
+            (Ok(s), Ok(e)) => ((e - s).num_minutes() + 24 * 60) % (24 * 60),
+            _ => task.time_total,
+        },
+        (None, None) => task.time_total,
+    };
+    task
+}
+
+/// brings the key of a task in a ledger of `version` up to the current version.
+pub fn upgrade_key(mut key: TaskKey, version: u32) -> TaskKey {
+    if version >= VERSION {
+        return key;
+    }
+    key.date = upgrade_date(&key.date);
+    key.time_start = upgrade_time(&key.time_start);
+    key
+}
+
+/// What [`migrate`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    /// copy of the ledger as it was before. none if it was already current.
+    pub backup: Option<PathBuf>,
+    pub tasks: usize,
+}
+
+/// rewrites the ledger `file` in the current version, after copying it to `<file>.v<n>.bak`.
+pub fn migrate(file: &str) -> Result<Migration> {
+    let from = version_of(file)?;
+    Header {
+        timr_version: from,
+        encryption: None,
+    }
+    .check()?;
+    let tasks = read_all_tasks(file)?;
+    if from == VERSION || !Path::new(file).exists() {
+        return Ok(Migration {
+            from,
+            to: VERSION,
+            backup: None,
+            tasks: tasks.len(),
+        });
+    }
+
+    let backup = PathBuf::from(format!("{}.v{}.bak", file, from));
+    std::fs::copy(file, &backup)?;
+    write_all_tasks(&tasks, file)?;
+    Ok(Migration {
+        from,
+        to: VERSION,
+        backup: Some(backup),
+        tasks: tasks.len(),
+    })
+}
+
+/// the copies [`migrate`] left of the ledger `file`, named `<file>.v<n>.bak`.
+pub fn backups(file: &str) -> Result<Vec<PathBuf>> {
+    let path = Path::new(file);
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(d), Some(n)) => (d, n.to_string_lossy().to_string()),
+        _ => return Ok(Vec::new()),
+    };
+    let dir = match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    };
+    let prefix = format!("{}.v", name);
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let n = entry.file_name().to_string_lossy().to_string();
+        let version = n.strip_prefix(&prefix).and_then(|r| r.strip_suffix(".bak"));
+        if version.is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit())) {
+            found.push(entry.path());
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::testing::temp_file;
+
+    #[test]
+    fn test_upgrade() {
+        let t = Task::new(
+            "2023-09-01".to_string(),
+            "debugging".to_string(),
+            "11:07:32".to_string(),
+            Some("16:00:53".to_string()),
+            290,
+        );
+        let t = upgrade(t, 0);
+        assert_eq!(t.date, "2023-9-1");
+        assert_eq!(
+            (t.time_start.as_str(), t.time_end.as_deref()),
+            ("1107", Some("1600"))
+        );
+        assert_eq!(t.time_total, 293);
+
+        let late = Task::new(
+            "2023-10-6".into(),
+            "deploy".into(),
+            "914".into(),
+            Some("2330".into()),
+            0,
+        );
+        assert_eq!(upgrade(late, 0).time_start, "0914");
+        let midnight = Task::new(
+            "2023-10-6".into(),
+            "deploy".into(),
+            "2300".into(),
+            Some("0100".into()),
+            -1320,
+        );
+        assert_eq!(upgrade(midnight.clone(), 0).time_total, 120);
+        assert_eq!(upgrade(midnight.clone(), VERSION), midnight);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let file = temp_file("schema_migrate");
+        // a ledger from before versioning, with an event appended to it.
+        let legacy = concat!(
+            r#"{"date":"2023-10-21","task_name":"writing software","time_start":"914","time_end":"1505","time_total":350}"#,
+            "\r\n",
+            r#"{"date":"2023-10-6","task_name":"running_task","time_start":"1830","time_end":null,"time_total":0}"#,
+            "\r\n",
+            r#"{"event":"end","task":{"date":"2023-10-6","task_name":"running_task","time_start":"1830","time_end":"1908","time_total":38}}"#,
+            "\n",
+        );
+        std::fs::write(&file, legacy).unwrap();
+        assert_eq!(version_of(&file).unwrap(), 0);
+
+        // old ledgers are read as if they were current.
+        let tasks = read_all_tasks(&file).unwrap();
+        assert_eq!(tasks[0].date, "2023-10-21");
+        assert_eq!(tasks[0].time_start, "0914");
+        assert_eq!(tasks[0].time_total, 351);
+        assert_eq!(tasks[1].time_end.as_deref(), Some("1908"));
+
+        let m = migrate(&file).unwrap();
+        assert_eq!((m.from, m.to, m.tasks), (0, VERSION, 2));
+        let backup = m.backup.unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), legacy);
+        assert_eq!(version_of(&file).unwrap(), VERSION);
+        assert_eq!(read_all_tasks(&file).unwrap(), tasks);
+        assert_eq!(migrate(&file).unwrap().backup, None);
+        _ = std::fs::remove_file(backup);
+
+        std::fs::write(&file, "{\"timr_version\":99}\n").unwrap();
+        assert!(read_all_tasks(&file).is_err());
+        assert!(migrate(&file).is_err());
+    }
+}
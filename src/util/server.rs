@@ -0,0 +1,573 @@
+use super::{
+    dates::parse_range,
+    service::{ListFilter, Query, Timr},
+    utility::parse_time,
+};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tiny_http::{Header, Method, Request, Response};
+
+/// API server settings, the `serve` section of the config.
+///
+/// # Example
+/// ```json
+/// "serve": { "port": 7878, "token": "s3cret", "origins": ["https://dash.example.com"] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    pub port: u16,
+    /// when set, requests have to send `Authorization: Bearer <token>`.
+    pub token: Option<String>,
+    /// web pages that may call the API, besides ones on localhost and browser extensions.
+    pub origins: Vec<String>,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            port: 7878,
+            token: None,
+            origins: Vec::new(),
+        }
+    }
+}
+
+/// the schemes browser extensions send as their `Origin`.
+const EXTENSION_SCHEMES: [&str; 3] = ["chrome-extension", "moz-extension", "safari-web-extension"];
+
+/// The body of `POST /start` and `POST /end`.
+#[derive(Debug, Deserialize)]
+struct TaskRequest {
+    task: String,
+    /// HHMM. defaults to now.
+    time: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A small JSON API over the ledger, listening on localhost only.
+/// requests for another host, or from a web page on a site not in the `origins` config, are refused.
+/// pages that are let in get CORS headers, and their preflight requests are answered.
+///
+/// * `GET /tasks`: the running tasks, or the tasks matching `on`, `from`, `to`, `name`, `tag`, and `limit`.
+/// * `GET /status`: the running tasks and today's and this week's totals.
+/// * `GET /report`: totals for today, or for the same parameters as `/tasks`.
+/// * `POST /start`: starts `{"task": "name", "time": "0900", "tags": []}`, the time and tags being optional.
+/// * `POST /end`: ends the running task `{"task": "name", "time": "1700"}`.
+///
+/// a request that does not make sense is answered with 400, and one the ledger could not
+/// serve, like a ledger that cannot be read or decrypted, with 500.
+pub struct Server {
+    http: tiny_http::Server,
+    timr: Timr,
+    token: Option<String>,
+    origins: Vec<String>,
+}
+
+impl Server {
+    /// listens on `port` on localhost. port 0 picks a free one.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the port is taken, or `token` could not be sent in a header.
+    pub fn bind(timr: Timr, port: u16, token: Option<String>) -> Result<Self> {
+        if let Some(token) = &token {
+            check_token(token)?;
+        }
+        let http = tiny_http::Server::http(("127.0.0.1", port))
+            .map_err(|e| anyhow!("could not listen on port {}: {}", port, e))?;
+        let origins = timr.config().serve.origins.clone();
+        Ok(Self {
+            http,
+            timr,
+            token,
+            origins,
+        })
+    }
+
+    /// the port the server is listening on.
+    pub fn port(&self) -> u16 {
+        self.http.server_addr().to_ip().map_or(0, |a| a.port())
+    }
+
+    /// answers requests until [`Server::stop`] is called.
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.respond(request);
+        }
+    }
+
+    /// makes [`Server::run`] return.
+    pub fn stop(&self) {
+        self.http.unblock();
+    }
+
+    /// the status and error to refuse `request` with, if it should be.
+    ///
+    /// a web page on any site can send requests to localhost, and with DNS rebinding it can
+    /// read the answers too. so the Host has to be localhost, and an Origin, if there is one,
+    /// has to be allowed. preflight requests do not carry the token, so they are not asked for it.
+    fn refusal(&self, request: &Request) -> Option<(u16, String)> {
+        if !header(request, "Host").is_some_and(is_local) {
+            return Some((403, error("only requests for localhost are answered")));
+        }
+        if let Some(origin) = header(request, "Origin") {
+            if !self.allows(origin) {
+                return Some((403, error(&format!("requests from {} are refused", origin))));
+            }
+        }
+        if let (Some(token), false) = (&self.token, request.method() == &Method::Options) {
+            let expected = format!("Bearer {}", token);
+            if !header(request, "Authorization")
+                .is_some_and(|a| same(a.as_bytes(), expected.as_bytes()))
+            {
+                return Some((401, error("a token is needed")));
+            }
+        }
+        None
+    }
+
+    /// whether a page at `origin` may call the API: one on localhost, a browser extension,
+    /// or one in the `origins` config.
+    fn allows(&self, origin: &str) -> bool {
+        let (scheme, host) = match origin.split_once("://") {
+            Some(parts) => parts,
+            None => return false,
+        };
+        is_local(host)
+            || EXTENSION_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+            || self
+                .origins
+                .iter()
+                .any(|o| o.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    }
+
+    fn respond(&self, mut request: Request) {
+        let (status, body) = match self.refusal(&request) {
+            Some(refused) => refused,
+            None if request.method() == &Method::Options => (204, String::new()),
+            None => {
+                let mut body = String::new();
+                match request.as_reader().read_to_string(&mut body) {
+                    Err(e) => (400, error(&e.to_string())),
+                    Ok(_) => {
+                        let method = request.method().clone();
+                        self.route(&method, request.url(), &body)
+                    }
+                }
+            }
+        };
+        let field = |name: &str, value: &str| Header::from_bytes(name, value).unwrap();
+        let mut response = Response::from_string(body).with_status_code(status);
+        if status != 204 {
+            response.add_header(field("Content-Type", "application/json"));
+        }
+        // let the page read the answer, and send the token and a JSON body with its requests.
+        if let Some(origin) = header(&request, "Origin").filter(|o| self.allows(o)) {
+            response.add_header(field("Access-Control-Allow-Origin", origin));
+            response.add_header(field("Vary", "Origin"));
+            if request.method() == &Method::Options {
+                response.add_header(field("Access-Control-Allow-Methods", "GET, POST, OPTIONS"));
+                response.add_header(field(
+                    "Access-Control-Allow-Headers",
+                    "Authorization, Content-Type",
+                ));
+                response.add_header(field("Access-Control-Max-Age", "600"));
+            }
+        }
+        // the client may have gone away, which is no reason to stop serving.
+        _ = request.respond(response);
+    }
+
+    /// the status and JSON body of the response to `method` on `url`.
+    fn route(&self, method: &Method, url: &str, body: &str) -> (u16, String) {
+        let (path, params) = match url.split_once('?') {
+            Some((path, query)) => (path, parse_query(query)),
+            None => (url, BTreeMap::new()),
+        };
+        // a request that does not make sense is the client's fault. anything that goes
+        // wrong after that, like a ledger that cannot be read, is ours.
+        let bad = |e: anyhow::Error| (400, error(&e.to_string()));
+        let answer = |result: Result<String>| match result {
+            Ok(json) => (200, json),
+            Err(e) => (500, error(&e.to_string())),
+        };
+        match (method, path.trim_end_matches('/')) {
+            (Method::Get, "/tasks") => match self.query(&params, ListFilter::Running) {
+                Ok(q) => answer(self.timr.query(&q).and_then(to_json)),
+                Err(e) => bad(e),
+            },
+            (Method::Get, "/status") => answer(self.timr.status().and_then(to_json)),
+            (Method::Get, "/report") => match self.query(&params, ListFilter::Today) {
+                Ok(q) => answer(self.timr.report(&q).and_then(to_json)),
+                Err(e) => bad(e),
+            },
+            (Method::Post, "/start") => match parse_body(body) {
+                Ok(r) => answer(
+                    self.timr
+                        .start_with_tags(&r.task, r.time.as_deref(), &r.tags)
+                        .and_then(to_json),
+                ),
+                Err(e) => bad(e),
+            },
+            (Method::Post, "/end") => match parse_body(body) {
+                Ok(r) => match self.timr.is_running(&r.task) {
+                    Ok(true) => {
+                        answer(self.timr.stop(&r.task, r.time.as_deref()).and_then(to_json))
+                    }
+                    Ok(false) => (
+                        404,
+                        error(&format!("there is no running task named {}", r.task)),
+                    ),
+                    Err(e) => answer(Err(e)),
+                },
+                Err(e) => bad(e),
+            },
+            (_, "/tasks" | "/status" | "/report" | "/start" | "/end") => (
+                405,
+                error(&format!("{} is not allowed on {}", method, path)),
+            ),
+            _ => (404, error(&format!("there is nothing at {}", path))),
+        }
+    }
+
+    /// the query in `params`, the same as the one `list` and `report` take on the command line.
+    /// without any dates, the tasks in `filter` are looked at.
+    fn query(&self, params: &BTreeMap<String, String>, filter: ListFilter) -> Result<Query> {
+        let today = self.timr.today();
+        let range = |s: &String| parse_range(s, today, self.timr.config().week_start);
+        let filter = match (params.get("on"), params.get("from"), params.get("to")) {
+            (Some(on), ..) => {
+                let (from, to) = range(on)?;
+                ListFilter::Between(Some(from), Some(to))
+            }
+            (None, None, None) => filter,
+            (None, from, to) => ListFilter::Between(
+                from.map(range).transpose()?.map(|r| r.0),
+                to.map(range).transpose()?.map(|r| r.1),
+            ),
+        };
+        let limit = match params.get("limit") {
+            Some(l) => Some(
+                l.parse()
+                    .map_err(|_| anyhow!("limit must be a number, not {}", l))?,
+            ),
+            None => None,
+        };
+        Ok(Query {
+            filter,
+            name: params.get("name").cloned(),
+            tag: params.get("tag").cloned(),
+            sort: None,
+            limit,
+        })
+    }
+}
+
+/// the value of the header `name` in `request`, if it was sent.
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// the task in `body`, checking its name and time before the ledger is touched.
+fn parse_body(body: &str) -> Result<TaskRequest> {
+    let r: TaskRequest = serde_json::from_str(body)
+        .map_err(|e| anyhow!("the body should be like {{\"task\": \"name\"}}: {}", e))?;
+    if r.task.trim().is_empty() {
+        bail!("a task needs a name");
+    }
+    if let Some(time) = &r.time {
+        parse_time(time)?;
+    }
+    Ok(r)
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<String> {
+    Ok(serde_json::to_string(&value)?)
+}
+
+fn error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// decodes `a=1&b=two%20words` into its parameters.
+fn parse_query(query: &str) -> BTreeMap<String, String> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.split_once('=') {
+            Some((k, v)) => (decode(k), decode(v)),
+            None => (decode(p), String::new()),
+        })
+        .collect()
+}
+
+/// undoes the percent encoding of a query parameter. `+` is a space.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            // from_str_radix would take a sign, so the digits are checked first.
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit) =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+                out.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 2;
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// whether `host`, with or without a port, is this machine.
+fn is_local(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => &host,
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// whether `a` and `b` are the same, taking as long wherever they differ,
+/// so that the token cannot be guessed a byte at a time.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// checks that `token` can be sent in a header.
+fn check_token(token: &str) -> Result<()> {
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_graphic()) {
+        bail!("the token must be printable ascii, without spaces");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::testing::temp_file;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+
+    /// sends a request and returns the status and the JSON it got back.
+    fn send(port: u16, request: &str, token: Option<&str>, body: &str) -> (u16, serde_json::Value) {
+        let auth = token.map_or(String::new(), |t| {
+            format!("Authorization: Bearer {}\r\n", t)
+        });
+        send_with(port, request, &format!("Host: localhost\r\n{}", auth), body)
+    }
+
+    /// sends a request with `headers`, each ending in `\r\n`.
+    fn send_with(port: u16, request: &str, headers: &str, body: &str) -> (u16, serde_json::Value) {
+        let (status, _, json) = exchange(port, request, headers, body);
+        (status, serde_json::from_str(&json).unwrap())
+    }
+
+    /// sends a request with `headers` and returns the status, headers, and body it got back.
+    fn exchange(port: u16, request: &str, headers: &str, body: &str) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "{} HTTP/1.1\r\n{}Connection: close\r\nContent-Length: {}\r\n\r\n{}",
+            request,
+            headers,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, head.to_string(), body.to_string())
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let params = parse_query("name=code+review&tag=%23ops&limit=5&empty&bad=%zz");
+        assert_eq!(params["name"], "code review");
+        assert_eq!(params["tag"], "#ops");
+        assert_eq!(params["empty"], "");
+        assert_eq!(params["bad"], "%zz");
+        // "+" is a space, not part of a number.
+        assert_eq!(decode("%+1%-1"), "% 1%-1");
+        assert_eq!(decode("100%"), "100%");
+    }
+
+    #[test]
+    fn test_api() {
+        let path = temp_file("server");
+        let timr = Timr::new(path.as_str());
+        assert!(Server::bind(timr.clone(), 0, Some("has space".into())).is_err());
+        let server = Arc::new(Server::bind(timr, 0, Some("s3cret".into())).unwrap());
+        let port = server.port();
+        let running = {
+            let server = server.clone();
+            std::thread::spawn(move || server.run())
+        };
+        let token = Some("s3cret");
+
+        assert_eq!(send(port, "GET /tasks", None, "").0, 401);
+        assert_eq!(send(port, "GET /tasks", Some("guess"), "").0, 401);
+        let (status, tasks) = send(port, "GET /tasks", token, "");
+        assert_eq!((status, tasks), (200, serde_json::json!([])));
+
+        let (status, task) = send(
+            port,
+            "POST /start",
+            token,
+            r#"{"task": "code review", "time": "0900"}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(task["time_start"], "0900");
+        let (_, status_now) = send(port, "GET /status", token, "");
+        assert_eq!(status_now["running"][0][0]["task_name"], "code review");
+        let (_, tasks) = send(port, "GET /tasks?name=code%20review", token, "");
+        assert_eq!(tasks.as_array().unwrap().len(), 1);
+
+        let (status, task) = send(
+            port,
+            "POST /end",
+            token,
+            r#"{"task": "code review", "time": "1030"}"#,
+        );
+        assert_eq!((status, task["time_total"].as_i64()), (200, Some(90)));
+        let (_, tasks) = send(port, "GET /tasks", token, "");
+        assert_eq!(tasks, serde_json::json!([]));
+        let (_, report) = send(port, "GET /report?on=today", token, "");
+        assert_eq!(report["total"], 90);
+
+        let (status, error) = send(port, "POST /end", token, r#"{"task": "code review"}"#);
+        assert_eq!(status, 404);
+        assert!(error["error"].as_str().unwrap().contains("no running task"));
+        assert_eq!(send(port, "POST /start", token, "not json").0, 400);
+        let late = r#"{"task": "email", "time": "25:00"}"#;
+        assert_eq!(send(port, "POST /start", token, late).0, 400);
+        assert!(!Timr::new(path.as_str()).is_running("email").unwrap());
+        assert_eq!(send(port, "GET /report?limit=many", token, "").0, 400);
+        assert_eq!(send(port, "DELETE /tasks", token, "").0, 405);
+        assert_eq!(send(port, "GET /nowhere", token, "").0, 404);
+
+        // a ledger that cannot be read is the server's fault, not the request's.
+        std::fs::write(&path, "not a ledger\n").unwrap();
+        let (status, error) = send(port, "GET /tasks", token, "");
+        assert_eq!(status, 500);
+        assert!(error["error"].as_str().unwrap().contains("error parsing"));
+        assert_eq!(send(port, "GET /status", token, "").0, 500);
+        assert_eq!(send(port, "GET /tasks?limit=many", token, "").0, 400);
+
+        server.stop();
+        running.join().unwrap();
+        _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_local_only() {
+        let server = Arc::new(Server::bind(Timr::new(temp_file("server_local")), 0, None).unwrap());
+        let port = server.port();
+        let running = {
+            let server = server.clone();
+            std::thread::spawn(move || server.run())
+        };
+        let status = |headers: &str| send_with(port, "GET /tasks", headers, "").0;
+
+        assert_eq!(status(&format!("Host: 127.0.0.1:{}\r\n", port)), 200);
+        assert_eq!(
+            status("Host: LOCALHOST\r\nOrigin: http://localhost:3000\r\n"),
+            200
+        );
+        assert_eq!(status("Host: [::1]\r\n"), 200);
+        // a page on another site, with its name pointed at 127.0.0.1.
+        assert_eq!(status(&format!("Host: evil.example:{}\r\n", port)), 403);
+        assert_eq!(status("Host: localhost.evil.example\r\n"), 403);
+        assert_eq!(
+            status("Host: localhost\r\nOrigin: https://evil.example\r\n"),
+            403
+        );
+        assert_eq!(status("Host: localhost\r\nOrigin: null\r\n"), 403);
+        assert_eq!(status(""), 403);
+
+        server.stop();
+        running.join().unwrap();
+    }
+
+    #[test]
+    fn test_cors() {
+        let mut config = crate::util::config::Config::default();
+        config.serve.origins = vec!["https://dash.example.com/".to_string()];
+        let timr = Timr::new(temp_file("server_cors")).with_config(config);
+        let server = Arc::new(Server::bind(timr, 0, Some("s3cret".into())).unwrap());
+        let port = server.port();
+        let running = {
+            let server = server.clone();
+            std::thread::spawn(move || server.run())
+        };
+        let auth = "Host: localhost\r\nAuthorization: Bearer s3cret\r\n";
+
+        // a browser extension starting a task.
+        let extension = "Origin: chrome-extension://abcdefghijklmnop\r\n";
+        let start = r#"{"task": "reading"}"#;
+        let (status, head, _) = exchange(
+            port,
+            "POST /start",
+            &format!("{}{}", auth, extension),
+            start,
+        );
+        assert_eq!(status, 200);
+        assert!(head.contains("Access-Control-Allow-Origin: chrome-extension://abcdefghijklmnop"));
+        let firefox = "Origin: moz-extension://0c2e6f7a\r\n";
+        assert_eq!(
+            exchange(port, "GET /status", &format!("{}{}", auth, firefox), "").0,
+            200
+        );
+
+        // a dashboard asking first whether it may send the token.
+        let preflight = "Host: localhost\r\nOrigin: https://dash.example.com\r\n\
+            Access-Control-Request-Method: POST\r\n\
+            Access-Control-Request-Headers: authorization, content-type\r\n";
+        let (status, head, empty) = exchange(port, "OPTIONS /end", preflight, "");
+        assert_eq!((status, empty.as_str()), (204, ""));
+        assert!(head.contains("Access-Control-Allow-Origin: https://dash.example.com"));
+        assert!(head.contains("Access-Control-Allow-Methods: GET, POST, OPTIONS"));
+        assert!(head.contains("Access-Control-Allow-Headers: Authorization, Content-Type"));
+        let dashboard = format!("{}Origin: https://dash.example.com\r\n", auth);
+        let (status, head, _) = exchange(port, "POST /end", &dashboard, r#"{"task": "reading"}"#);
+        assert_eq!(status, 200);
+        assert!(head.contains("Access-Control-Allow-Origin: https://dash.example.com"));
+
+        // other sites get neither an answer nor the headers.
+        let other = "Host: localhost\r\nOrigin: https://evil.example\r\n";
+        let (status, head, _) = exchange(port, "OPTIONS /end", other, "");
+        assert_eq!(status, 403);
+        assert!(!head.contains("Access-Control-Allow-Origin"));
+        assert_eq!(
+            exchange(port, "OPTIONS /end", "Host: evil.example\r\n", "").0,
+            403
+        );
+
+        server.stop();
+        running.join().unwrap();
+    }
+
+    #[test]
+    fn test_same() {
+        assert!(same(b"Bearer s3cret", b"Bearer s3cret"));
+        assert!(!same(b"Bearer s3cret", b"Bearer s3creT"));
+        assert!(!same(b"Bearer s3cret", b"Bearer s3cre"));
+        assert!(!same(b"", b"x"));
+    }
+}
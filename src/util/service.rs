@@ -0,0 +1,1564 @@
+use super::{
+    archive,
+    billing::Invoice,
+    config::Config,
+    duration::{Duration, DurationStyle},
+    encryption::{self, Secret},
+    events::{self, Event},
+    git::{BranchAs, Commit, Repo},
+    rounding::{RoundingScope, TimecardRounding},
+    sync,
+    targets::Balance,
+    tasks::Task,
+    templates::Template,
+    utility::*,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use serde::Serialize;
+use std::path::Path;
+
+/// Which tasks [`Timr::list`] and [`Timr::report`] should look at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListFilter {
+    /// tasks that have not been ended yet.
+    #[default]
+    Running,
+    /// tasks started today.
+    Today,
+    /// tasks started this week.
+    Week,
+    /// tasks started last week.
+    LastWeek,
+    /// tasks started in ISO week `n` of this ISO week-year.
+    IsoWeek(u32),
+    /// tasks started within the last `n` days.
+    Days(i32),
+    /// tasks started between two days, both included. an open end is unbounded.
+    Between(Option<NaiveDate>, Option<NaiveDate>),
+}
+
+/// How to order the tasks returned by [`Timr::query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// oldest first.
+    Date,
+    Name,
+    /// longest first.
+    Duration,
+}
+
+/// A [`ListFilter`] narrowed down by name or tag, then sorted and limited.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Query {
+    pub filter: ListFilter,
+    /// only tasks whose name contains this, ignoring case.
+    pub name: Option<String>,
+    /// only tasks with this tag.
+    pub tag: Option<String>,
+    /// order of the results. ledger order (newest first) if not set.
+    pub sort: Option<Sort>,
+    /// return at most this many tasks.
+    pub limit: Option<usize>,
+}
+
+impl From<ListFilter> for Query {
+    fn from(filter: ListFilter) -> Self {
+        Self {
+            filter,
+            ..Default::default()
+        }
+    }
+}
+
+/// When a task passed to [`Timr::log`] happened. any two of `from`, `to`, and `minutes` are
+/// enough; with only `minutes` the task ends now.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LogTimes {
+    /// the day it started. defaults to today.
+    pub date: Option<NaiveDate>,
+    /// time started (HHMM).
+    pub from: Option<String>,
+    /// time ended (HHMM).
+    pub to: Option<String>,
+    pub minutes: Option<i64>,
+}
+
+/// Minutes logged against one task name or one day, with and without rounding.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Total {
+    /// the task name, or the date for a day total.
+    pub name: String,
+    pub minutes: i64,
+    pub rounded: i64,
+}
+
+/// Time spent per task name and per day over a set of tasks.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Report {
+    /// the tasks the report was built from.
+    pub tasks: Vec<Task>,
+    /// totals per task name, in order of first appearance.
+    /// when rounding per day these are not rounded.
+    pub totals: Vec<Total>,
+    /// totals per day, in order of first appearance.
+    pub days: Vec<Total>,
+    /// totals per git branch, in order of first appearance. tasks without a branch are left out.
+    pub branches: Vec<Total>,
+    /// total minutes across every task.
+    pub total: i64,
+    /// total minutes across every task, after rounding.
+    pub rounded_total: i64,
+}
+
+impl Report {
+    pub fn from_tasks(tasks: Vec<Task>, rounding: &TimecardRounding) -> Self {
+        let mut totals: Vec<Total> = Vec::new();
+        let mut days: Vec<Total> = Vec::new();
+        let mut branches: Vec<Total> = Vec::new();
+        for t in &tasks {
+            let rounded = rounding.entry(t.time_total);
+            let mut lists = vec![(&mut totals, &t.task_name), (&mut days, &t.date)];
+            if let Some(branch) = &t.branch {
+                lists.push((&mut branches, branch));
+            }
+            for (list, name) in lists {
+                match list.iter_mut().find(|total| total.name == *name) {
+                    Some(total) => {
+                        total.minutes += t.time_total;
+                        total.rounded += rounded;
+                    }
+                    None => list.push(Total {
+                        name: name.clone(),
+                        minutes: t.time_total,
+                        rounded,
+                    }),
+                }
+            }
+        }
+        for day in days.iter_mut() {
+            day.rounded = rounding.day(day.minutes, day.rounded);
+        }
+        let total = days.iter().map(|d| d.minutes).sum();
+        let rounded_total = days.iter().map(|d| d.rounded).sum();
+        Self {
+            tasks,
+            totals,
+            days,
+            branches,
+            total,
+            rounded_total,
+        }
+    }
+}
+
+impl Report {
+    /// the report as csv: one row per entry, or one row per day when rounding per day.
+    /// minutes are followed by the same lengths formatted in `style`.
+    pub fn to_csv(
+        &self,
+        per: RoundingScope,
+        rounding: &TimecardRounding,
+        style: DurationStyle,
+    ) -> String {
+        let fmt = |minutes: i64| csv_field(&Duration::from_minutes(minutes).format(style));
+        let mut s = String::new();
+        match per {
+            RoundingScope::Entry => {
+                s += "date,task_name,time_start,time_end,minutes,rounded,duration,rounded_duration\n";
+                for t in &self.tasks {
+                    let rounded = rounding.entry(t.time_total);
+                    s += &format!(
+                        "{},{},{},{},{},{},{},{}\n",
+                        t.date,
+                        csv_field(&t.task_name),
+                        t.time_start,
+                        t.time_end.clone().unwrap_or_default(),
+                        t.time_total,
+                        rounded,
+                        fmt(t.time_total),
+                        fmt(rounded)
+                    );
+                }
+            }
+            RoundingScope::Day => {
+                s += "date,minutes,rounded,duration,rounded_duration\n";
+                for d in &self.days {
+                    s += &format!(
+                        "{},{},{},{},{}\n",
+                        d.name,
+                        d.minutes,
+                        d.rounded,
+                        fmt(d.minutes),
+                        fmt(d.rounded)
+                    );
+                }
+            }
+        }
+        s
+    }
+}
+
+/// quotes a csv field if it needs it.
+fn csv_field(s: &str) -> String {
+    match s.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+/// What is being worked on right now.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Status {
+    /// tasks that have not been ended, with the minutes spent on each so far.
+    pub running: Vec<(Task, i64)>,
+    /// minutes logged today, including time on running tasks.
+    pub today_total: i64,
+    /// minutes expected today.
+    pub today_target: i64,
+    /// minutes logged this week, including time on running tasks.
+    pub week_total: i64,
+    /// minutes expected this week.
+    pub week_target: i64,
+}
+
+/// The time tracking service. every front end (the cli included) goes through this,
+/// so that none of them have to know how the ledger is stored.
+///
+/// # Example
+/// ```no_run
+/// use timr::Timr;
+///
+/// let timr = Timr::new("timr.json");
+/// let task = timr.start("code review", Some("0900"))?;
+/// let task = timr.stop(&task.task_name, Some("0945"))?;
+/// assert_eq!(task.time_total, 45);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timr {
+    file: String,
+    config: Config,
+}
+
+impl Default for Timr {
+    fn default() -> Self {
+        Self::new(OUTPUT_FILE)
+    }
+}
+
+impl Timr {
+    /// creates a service that reads and writes tasks in `file`.
+    pub fn new(file: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            config: Config::default(),
+        }
+    }
+
+    /// replaces the default settings with `config`. a `key_file` in its `encryption`
+    /// section unlocks the ledger if it is encrypted.
+    pub fn with_config(mut self, config: Config) -> Self {
+        if let Some(secret) = config.encryption.secret() {
+            encryption::set_secret(&self.file, secret);
+        }
+        self.config = config;
+        self
+    }
+
+    /// unlocks the ledger, if it is encrypted, and its archive with `secret`.
+    /// it is used for this ledger from then on, instead of a key file or the environment.
+    pub fn with_secret(self, secret: Secret) -> Self {
+        encryption::set_secret(&self.file, secret);
+        self
+    }
+
+    /// the ledger file this service works on.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// rewrites the ledger as a snapshot of its tasks, replaying the events appended since
+    /// the last one. returns the number of tasks kept.
+    pub fn compact(&self) -> Result<usize> {
+        compact(&self.file)
+    }
+
+    /// returns `true` if there is a task by that name that has not been ended.
+    pub fn is_running(&self, task_name: &str) -> Result<bool> {
+        check_if_task_exists(task_name, &self.file)
+    }
+
+    /// starts a task at `time` (HHMM), or now if no time is given.
+    pub fn start(&self, task_name: &str, time: Option<&str>) -> Result<Task> {
+        self.start_with_tags(task_name, time, &[])
+    }
+
+    /// same as [`Timr::start`], labelling the new task with `tags`.
+    pub fn start_with_tags(
+        &self,
+        task_name: &str,
+        time: Option<&str>,
+        tags: &[String],
+    ) -> Result<Task> {
+        let mut t = Task::new(String::new(), task_name.to_owned(), String::new(), None, 0);
+        t.tags = tags.to_vec();
+        self.start_task(t, time)
+    }
+
+    /// starts a copy of `task` today at `time` (HHMM), or now if no time is given.
+    /// its name, tags, and project are kept, everything else is filled in.
+    /// with git enabled, the branch and repository of the working directory are recorded.
+    pub fn start_task(&self, mut task: Task, time: Option<&str>) -> Result<Task> {
+        let start = self.at(time)?;
+        if self.config.git.enabled && task.repo.is_none() {
+            if let Some(repo) = std::env::current_dir()
+                .ok()
+                .and_then(|d| Repo::discover(&d))
+            {
+                task.branch = repo.branch()?;
+                task.repo = Some(repo.work_tree.to_string_lossy().to_string());
+            }
+        }
+        let mut t = Task {
+            date: format_date(start.date_naive()),
+            time_start: start.format("%H%M").to_string(),
+            time_end: None,
+            time_total: 0,
+            invoice: None,
+            started_at: Some(start),
+            ended_at: None,
+            id: None,
+            ..task
+        };
+        t.touch(self.now());
+        output_task_to_file(t.clone(), &self.file)?;
+        Ok(t)
+    }
+
+    /// writes a finished copy of `task` that ran from `start` to `end`.
+    /// its name, tags, and project are kept, everything else is filled in.
+    pub fn log_task(
+        &self,
+        task: Task,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Result<Task> {
+        let mut t = Task {
+            date: format_date(start.date_naive()),
+            time_start: start.format("%H%M").to_string(),
+            time_end: None,
+            invoice: None,
+            started_at: Some(start),
+            id: None,
+            ..task
+        };
+        self.end_task(&mut t, end)?;
+        t.touch(self.now());
+        output_task_to_file(t.clone(), &self.file)?;
+        Ok(t)
+    }
+
+    /// the template called `name` in the config.
+    pub fn template(&self, name: &str) -> Result<&Template> {
+        match self.config.templates.get(name) {
+            Some(t) => Ok(t),
+            None => bail!("there is no template named {}", name),
+        }
+    }
+
+    /// logs a finished task named `name`, or from the template called `name`, at `times`.
+    /// a template's length is used when no duration or end time is given.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the times are incomplete, the task would end in the future,
+    /// or, unless `allow_overlap` is set, it overlaps another task.
+    pub fn log(&self, task: Task, times: &LogTimes, allow_overlap: bool) -> Result<Task> {
+        let (task, length) = match self.config.templates.get(&task.task_name) {
+            Some(template) => {
+                let mut t = template.task(&task.task_name);
+                t.tags.extend(task.tags);
+                t.project = task.project.or(t.project);
+                (t, Some(template.minutes))
+            }
+            None => (task, None),
+        };
+        let (start, end) = self.log_span(times, length)?;
+        self.check_entry(start, end, allow_overlap)?;
+        self.log_task(task, start, end)
+    }
+
+    /// when a task logged at `times` starts and ends. an end before the start is on the next day.
+    fn log_span(
+        &self,
+        times: &LogTimes,
+        length: Option<i64>,
+    ) -> Result<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let today = self.today();
+        let date = times.date.unwrap_or(today);
+        let tz = self.config.timezone;
+        let on = |date: NaiveDate, time: &str| resolve_local(date, parse_time(time)?, tz);
+        if let (Some(_), Some(_), Some(_)) = (&times.from, &times.to, times.minutes) {
+            bail!("give at most two of a start time, an end time, and a duration");
+        }
+        let length = times.minutes.or(length).map(chrono::Duration::minutes);
+        Ok(match (&times.from, &times.to, length) {
+            (Some(from), Some(to), _) => {
+                let start = on(date, from)?;
+                let end = on(date, to)?;
+                match end <= start {
+                    true => (start, on(date.succ_opt().unwrap(), to)?),
+                    false => (start, end),
+                }
+            }
+            (Some(from), None, Some(length)) => {
+                let start = on(date, from)?;
+                (start, start + length)
+            }
+            (None, Some(to), Some(length)) => {
+                let end = on(date, to)?;
+                (end - length, end)
+            }
+            (None, None, Some(length)) if date == today => (self.now() - length, self.now()),
+            (None, None, Some(_)) => bail!("give a start or end time to log a task on {}", date),
+            _ => bail!("give an end time or a duration"),
+        })
+    }
+
+    /// checks that a task from `start` to `end` is in the past and, unless `allow_overlap` is set,
+    /// does not overlap another task.
+    pub fn check_entry(
+        &self,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+        allow_overlap: bool,
+    ) -> Result<()> {
+        if end <= start {
+            bail!("a task has to end after it starts");
+        }
+        if end > self.now() {
+            bail!(
+                "the task would end in the future, at {}",
+                end.format("%Y-%m-%d %H%M")
+            );
+        }
+        if allow_overlap {
+            return Ok(());
+        }
+        // tasks from the day before can run past midnight.
+        let from = start.date_naive() - chrono::Duration::days(1);
+        for t in read_tasks_between(Some(from), end.date_naive(), &self.file)? {
+            let (s, e) = self.span(&t)?;
+            if s < end && start < e {
+                let t = self.localize(&t);
+                bail!(
+                    "it overlaps {} on {}, {} to {}",
+                    t.task_name,
+                    t.date,
+                    t.time_start,
+                    t.time_end.unwrap_or("now".to_string())
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// logs every recurring task that happens between `from` and `to`, both included.
+    /// holidays are skipped, as are days that already have a task by that name and
+    /// tasks that would not have ended yet.
+    ///
+    /// # Errors
+    ///
+    /// returns an error, and logs nothing, if one of the tasks would overlap another.
+    pub fn fill(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Task>> {
+        let existing = read_tasks_between(Some(from), to, &self.file)?;
+        let mut planned: Vec<(Task, DateTime<FixedOffset>, DateTime<FixedOffset>)> = Vec::new();
+        let days = from
+            .iter_days()
+            .take_while(|d| *d <= to)
+            .filter(|d| !self.config.targets.holidays.contains(d));
+        for day in days {
+            for r in self.config.recurring.iter().filter(|r| r.happens_on(day)) {
+                let template = self.template(&r.template)?;
+                let task = template.task(&r.template);
+                if existing
+                    .iter()
+                    .any(|t| t.task_name == task.task_name && t.naive_date() == Some(day))
+                {
+                    continue;
+                }
+                let start = resolve_local(day, parse_time(&r.start)?, self.config.timezone)?;
+                let end = start + chrono::Duration::minutes(template.minutes);
+                if end > self.now() {
+                    continue;
+                }
+                self.check_entry(start, end, false)
+                    .with_context(|| format!("cannot fill {} on {}", task.task_name, day))?;
+                if let Some((t, _, _)) = planned.iter().find(|(_, s, e)| *s < end && start < *e) {
+                    bail!(
+                        "{} and {} would overlap on {}",
+                        t.task_name,
+                        task.task_name,
+                        day
+                    );
+                }
+                planned.push((task, start, end));
+            }
+        }
+        planned
+            .into_iter()
+            .map(|(task, start, end)| self.log_task(task, start, end))
+            .collect()
+    }
+
+    /// starts the most recent task named `task_name` again now, with the same tags and project.
+    /// with no name, the most recently started task that has ended is continued.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if there is no such task, or it is still running.
+    pub fn continue_task(&self, task_name: Option<&str>) -> Result<Task> {
+        let tasks = read_all_tasks(&self.file)?;
+        let last = match task_name {
+            Some(name) => tasks.into_iter().find(|t| t.task_name == name),
+            None => tasks.into_iter().find(|t| t.time_end.is_some()),
+        };
+        match last {
+            Some(t) if t.time_end.is_none() => bail!("{} is still running", t.task_name),
+            Some(t) => self.start_task(t, None),
+            None => bail!("there is no task to continue"),
+        }
+    }
+
+    /// ends the most recent running task by that name at `time` (HHMM), or now if no time is given.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if there is no running task named `task_name`, or it started after `time`.
+    pub fn stop(&self, task_name: &str, time: Option<&str>) -> Result<Task> {
+        self.stop_at(task_name, self.at(time)?)
+    }
+
+    /// ends the most recent running task by that name at `end`, which need not be today.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if there is no running task named `task_name`, or it started after `end`.
+    pub fn stop_at(&self, task_name: &str, end: DateTime<FixedOffset>) -> Result<Task> {
+        let mut t = match get_task(task_name, Some(&self.file), false)? {
+            Some(t) => t,
+            None => bail!("there is no running task named {}", task_name),
+        };
+        self.end_task(&mut t, end)?;
+        t.touch(self.now());
+        update_task_in_file(t.clone(), &self.file)?;
+        Ok(t)
+    }
+
+    /// ends running tasks and starts `task_name` at the same `time` (HHMM), or now if no time
+    /// is given, in a single write to the ledger.
+    ///
+    /// every running task is ended unless `from` names the one to end.
+    /// returns the ended tasks and the new one.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if `from` is given and there is no running task by that name,
+    /// or a task to end started after `time`.
+    pub fn switch(
+        &self,
+        task_name: &str,
+        from: Option<&str>,
+        time: Option<&str>,
+    ) -> Result<(Vec<Task>, Task)> {
+        let at = self.at(time)?;
+
+        let mut ended = Vec::new();
+        for mut t in read_incomplete_tasks(&self.file)? {
+            if from.is_none_or(|name| t.task_name == name) {
+                self.end_task(&mut t, at)?;
+                t.touch(self.now());
+                ended.push(t);
+            }
+        }
+        if let (Some(name), true) = (from, ended.is_empty()) {
+            bail!("there is no running task named {}", name);
+        }
+
+        let mut started = Task::new(
+            format_date(at.date_naive()),
+            task_name.to_owned(),
+            at.format("%H%M").to_string(),
+            None,
+            0,
+        );
+        started.started_at = Some(at);
+        started.touch(self.now());
+        let mut changes: Vec<Event> = ended
+            .iter()
+            .map(|t| Event::End { task: t.clone() })
+            .collect();
+        changes.push(Event::Start {
+            task: started.clone(),
+        });
+        events::append(&changes, &self.file)?;
+        Ok((ended, started))
+    }
+
+    /// the current time in the configured time zone.
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        now_in(self.config.timezone)
+    }
+
+    /// today's date in the configured time zone.
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// the instant the clock reads `time` (HHMM) today, or now if no time is given.
+    fn at(&self, time: Option<&str>) -> Result<DateTime<FixedOffset>> {
+        match time {
+            Some(t) => resolve_local(self.today(), parse_time(t)?, self.config.timezone),
+            None => Ok(self.now()),
+        }
+    }
+
+    /// a task named after the git branch checked out in `dir`, to start when no name is given.
+    /// with `branch_as` set to `tag`, it is named after the repository and tagged with the branch.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if git is not enabled, `dir` is not in a repository,
+    /// or no branch is checked out.
+    pub fn branch_task(&self, dir: &Path) -> Result<Task> {
+        if !self.config.git.enabled {
+            bail!("a task name is required. enable `git` in the config to use the branch instead");
+        }
+        let repo = match Repo::discover(dir) {
+            Some(r) => r,
+            None => bail!("{} is not in a git repository", dir.display()),
+        };
+        let branch = match repo.branch()? {
+            Some(b) => b,
+            None => bail!("no branch is checked out in {}", repo.work_tree.display()),
+        };
+        let mut t = Task::default();
+        match self.config.git.branch_as {
+            BranchAs::Name => t.task_name = branch.clone(),
+            BranchAs::Tag => {
+                t.task_name = repo.name();
+                t.tags.push(branch.clone());
+            }
+        }
+        t.branch = Some(branch);
+        t.repo = Some(repo.work_tree.to_string_lossy().to_string());
+        Ok(t)
+    }
+
+    /// the commits made in `task`'s repository while it ran, newest first.
+    /// tasks without a repository use the one the working directory is in.
+    pub fn commits(&self, task: &Task) -> Result<Vec<Commit>> {
+        let dir = match &task.repo {
+            Some(r) => r.into(),
+            None => std::env::current_dir()?,
+        };
+        let repo = match Repo::discover(&dir) {
+            Some(r) => r,
+            None => bail!("{} is not in a git repository", dir.display()),
+        };
+        let (from, to) = self.span(task)?;
+        repo.commits_between(from, to)
+    }
+
+    /// ends the running `task` at `end`, or at its start if `end` is earlier.
+    /// unlike [`Timr::stop`], `end` can be on a later day than the start.
+    pub fn close(&self, task: &Task, end: DateTime<FixedOffset>) -> Result<Task> {
+        let (start, _) = self.span(task)?;
+        let end = end.max(start);
+        let mut t = task.clone();
+        t.time_end = Some(end.format("%H%M").to_string());
+        t.ended_at = task.started_at.map(|_| end);
+        t.time_total = (end - start).num_minutes();
+        t.touch(self.now());
+        replace_task_in_file(task, t.clone(), &self.file)?;
+        Ok(t)
+    }
+
+    /// ends `t` at `end`, counting from its start on the day it began. like [`Timr::close`],
+    /// a task without timestamps is not given an end timestamp.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if `end` is before the task started.
+    fn end_task(&self, t: &mut Task, end: DateTime<FixedOffset>) -> Result<()> {
+        let (start, _) = self.span(t)?;
+        if end < start {
+            bail!(
+                "{} started at {}, after the end time {}",
+                t.task_name,
+                self.localize(t).time_start,
+                end.format("%H%M")
+            );
+        }
+        t.time_end = Some(end.format("%H%M").to_string());
+        t.ended_at = t.started_at.map(|_| end);
+        t.time_total = (end - start).num_minutes();
+        Ok(())
+    }
+
+    /// the moment `time` (HHMM) falls on during `task`: on the day it started,
+    /// or the day after if that would be before the start.
+    pub fn time_during(&self, task: &Task, time: &str) -> Result<DateTime<FixedOffset>> {
+        let (start, _) = self.span(task)?;
+        let tz = self.config.timezone;
+        let date = match tz {
+            Some(tz) => start.with_timezone(&tz).date_naive(),
+            None => start.with_timezone(&chrono::Local).date_naive(),
+        };
+        let time = parse_time(time)?;
+        let t = resolve_local(date, time, tz)?;
+        match t < start {
+            true => resolve_local(date.succ_opt().unwrap(), time, tz),
+            false => Ok(t),
+        }
+    }
+
+    /// when `task` started and ended, or now if it is still running.
+    /// tasks without timestamps are taken to be in the configured time zone.
+    pub(crate) fn span(
+        &self,
+        task: &Task,
+    ) -> Result<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let date = match task.naive_date() {
+            Some(d) => d,
+            None => bail!("{} has no valid date", task.task_name),
+        };
+        let tz = self.config.timezone;
+        let from = match task.started_at {
+            Some(s) => s,
+            None => resolve_local(date, parse_time(&task.time_start)?, tz)?,
+        };
+        let to = match (task.ended_at, &task.time_end) {
+            (Some(e), _) => e,
+            (None, Some(end)) => {
+                let end = resolve_local(date, parse_time(end)?, tz)?;
+                match end < from {
+                    true => end + chrono::Duration::days(1),
+                    false => end,
+                }
+            }
+            (None, None) => self.now(),
+        };
+        Ok((from, to))
+    }
+
+    /// `task` with its date and times shown in the configured time zone.
+    pub fn localize(&self, task: &Task) -> Task {
+        task.localize(self.config.timezone)
+    }
+
+    /// returns the tasks matching `filter`.
+    pub fn list(&self, filter: ListFilter) -> Result<Vec<Task>> {
+        self.query(&filter.into())
+    }
+
+    /// the first and last day covered by `filter`, or `None` for running tasks.
+    pub fn date_range(&self, filter: ListFilter) -> Result<Option<(Option<NaiveDate>, NaiveDate)>> {
+        let today = self.today();
+        let first_day = self.config.week_start;
+        let week = |start: NaiveDate| (Some(start), start + chrono::Duration::days(6));
+        Ok(Some(match filter {
+            ListFilter::Running => return Ok(None),
+            ListFilter::Today => (Some(today), today),
+            ListFilter::Week => week(week_start(today, first_day)),
+            ListFilter::LastWeek => week(week_start(today, first_day) - chrono::Duration::days(7)),
+            ListFilter::IsoWeek(n) => {
+                let year = today.iso_week().year();
+                match iso_week_start(year, n, first_day) {
+                    Some(start) => week(start),
+                    None => bail!("{} has no week {}", year, n),
+                }
+            }
+            ListFilter::Days(days) => (Some(today - chrono::Duration::days(days as i64)), today),
+            ListFilter::Between(from, to) => (from, to.unwrap_or(NaiveDate::MAX)),
+        }))
+    }
+
+    /// returns the tasks matching `query`.
+    pub fn query(&self, query: &Query) -> Result<Vec<Task>> {
+        let mut tasks = match self.date_range(query.filter)? {
+            None => read_incomplete_tasks(&self.file)?,
+            Some((from, to)) => read_tasks_between(from, to, &self.file)?,
+        };
+
+        if let Some(name) = &query.name {
+            let name = name.to_lowercase();
+            tasks.retain(|t| t.task_name.to_lowercase().contains(&name));
+        }
+        if let Some(tag) = &query.tag {
+            tasks.retain(|t| t.has_tag(tag));
+        }
+        match query.sort {
+            Some(Sort::Date) => {
+                tasks.sort_by_key(|t| (t.naive_date(), parse_time(&t.time_start).ok()))
+            }
+            Some(Sort::Name) => tasks.sort_by(|a, b| a.task_name.cmp(&b.task_name)),
+            Some(Sort::Duration) => tasks.sort_by_key(|t| std::cmp::Reverse(t.time_total)),
+            None => (),
+        }
+        if let Some(limit) = query.limit {
+            tasks.truncate(limit);
+        }
+        Ok(tasks)
+    }
+
+    /// returns every task named `task_name` from the last `days` days.
+    pub fn find(&self, task_name: &str, days: i32) -> Result<Vec<Task>> {
+        Ok(read_tasks_from_day_range(days, self.today(), &self.file)?
+            .into_iter()
+            .filter(|t| t.task_name == task_name)
+            .collect())
+    }
+
+    /// the names of the running tasks, then of the tasks started in the last `days` days,
+    /// most recent first and without repeats.
+    pub fn task_names(&self, days: i32) -> Result<Vec<String>> {
+        let running = read_incomplete_tasks(&self.file)?;
+        let recent = read_tasks_from_day_range(days, self.today(), &self.file)?;
+        let mut names: Vec<String> = Vec::new();
+        for t in running.into_iter().chain(recent) {
+            if !names.contains(&t.task_name) {
+                names.push(t.task_name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// totals the time spent on the tasks matching `query`.
+    pub fn report(&self, query: &Query) -> Result<Report> {
+        Ok(Report::from_tasks(
+            self.query(query)?,
+            &self.config.rounding,
+        ))
+    }
+
+    /// the running tasks and how much has been logged today.
+    pub fn status(&self) -> Result<Status> {
+        let mut running = Vec::new();
+        for t in read_incomplete_tasks(&self.file)? {
+            let elapsed = task_elapsed(&t, self.config.timezone)?.num_minutes();
+            running.push((t, elapsed));
+        }
+
+        let today = self.today();
+        let mut today_total = 0;
+        for t in read_tasks_between(Some(today), today, &self.file)? {
+            today_total += match t.time_end {
+                Some(_) => t.time_total,
+                None => task_elapsed(&t, self.config.timezone)?.num_minutes(),
+            };
+        }
+        // tasks carried over from a previous day only count from midnight.
+        let midnight = resolve_local(today, chrono::NaiveTime::MIN, self.config.timezone)?;
+        let since_midnight = (self.now() - midnight).num_minutes();
+        for (t, elapsed) in &running {
+            if t.naive_date() != Some(today) {
+                today_total += (*elapsed).min(since_midnight);
+            }
+        }
+
+        let first_day = week_start(today, self.config.week_start);
+        let week_before_today: i64 = read_tasks_between(Some(first_day), today, &self.file)?
+            .iter()
+            .filter(|t| t.time_end.is_some() && t.naive_date() != Some(today))
+            .map(|t| t.time_total)
+            .sum();
+        let targets = &self.config.targets;
+
+        Ok(Status {
+            running,
+            today_total,
+            today_target: targets.daily_minutes(today),
+            week_total: week_before_today + today_total,
+            week_target: targets.weekly_minutes(first_day),
+        })
+    }
+
+    /// overtime across weeks, from the configured targets and the ended tasks in the ledger and its archive.
+    pub fn balance(&self) -> Result<Balance> {
+        let tasks = read_tasks_between(None, NaiveDate::MAX, &self.file)?;
+        let today = self.today();
+        let since = match self.config.targets.since {
+            Some(since) => since,
+            None => tasks
+                .iter()
+                .filter_map(|t| t.naive_date())
+                .min()
+                .unwrap_or(today),
+        };
+        Ok(self
+            .config
+            .targets
+            .balance(&tasks, since, today, self.config.week_start))
+    }
+
+    /// bills the ended tasks from `from` to `to` that have not been billed yet.
+    ///
+    /// unless this is a `preview`, the tasks are marked with the new invoice number, in the
+    /// ledger or in the archive segment they are in, so that they are not billed again.
+    pub fn invoice(
+        &self,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        preview: bool,
+    ) -> Result<Invoice> {
+        let mut collection = read_all_tasks(&self.file)?;
+        let billing = &self.config.billing;
+        // billed tasks may have been archived, so their numbers are looked up there too.
+        let all = read_tasks_between(None, NaiveDate::MAX, &self.file)?;
+        let number = billing.next_invoice_number(&all);
+
+        let tasks: Vec<Task> = read_tasks_between(from, to, &self.file)?
+            .into_iter()
+            .filter(|t| t.time_end.is_some() && t.invoice.is_none())
+            .collect();
+        if tasks.is_empty() {
+            bail!("there is nothing to bill for this period");
+        }
+        let invoice = billing.invoice(number, from, to, tasks)?;
+
+        if !preview {
+            for t in collection.iter_mut() {
+                if t.invoice.is_none() && invoice.tasks.contains(t) {
+                    t.invoice = Some(invoice.number.clone());
+                    t.touch(self.now());
+                }
+            }
+            write_all_tasks(&collection, &self.file)?;
+            let period = (from, to);
+            archive::mark_invoiced(
+                &invoice.tasks,
+                &invoice.number,
+                period,
+                self.now(),
+                &self.file,
+            )?;
+        }
+        Ok(invoice)
+    }
+
+    /// changes the start and/or end time (HHMM) of `task`, recalculating its total.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if a time cannot be parsed, or `task` is not in the ledger.
+    pub fn amend(&self, task: &Task, start: Option<&str>, end: Option<&str>) -> Result<Task> {
+        let mut amended = task.clone();
+        if let Some(start) = start {
+            amended.time_start = parse_time(start)?.format("%H%M").to_string();
+        }
+        if let Some(end) = end {
+            amended.time_end = Some(parse_time(end)?.format("%H%M").to_string());
+        }
+
+        // keep the timestamps in step. an end before the start is taken to be the next day.
+        if let (Some(date), true) = (task.naive_date(), task.started_at.is_some()) {
+            let tz = self.config.timezone;
+            let started = resolve_local(date, parse_time(&amended.time_start)?, tz)?;
+            amended.started_at = Some(started);
+            if let Some(end) = &amended.time_end {
+                let mut ended = resolve_local(date, parse_time(end)?, tz)?;
+                if ended < started {
+                    ended = resolve_local(date.succ_opt().unwrap(), parse_time(end)?, tz)?;
+                }
+                amended.ended_at = Some(ended);
+            }
+        }
+
+        if amended.time_end.is_some() {
+            let (from, to) = self.span(&amended)?;
+            amended.time_total = (to - from).num_minutes();
+        }
+        amended.touch(self.now());
+        replace_task_in_file(task, amended.clone(), &self.file)?;
+        Ok(amended)
+    }
+
+    /// renames `task` to `name`.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the name is blank, or `task` is not in the ledger.
+    pub fn rename(&self, task: &Task, name: &str) -> Result<Task> {
+        if name.trim().is_empty() {
+            bail!("a task needs a name");
+        }
+        let mut renamed = task.clone();
+        renamed.task_name = name.trim().to_string();
+        renamed.touch(self.now());
+        replace_task_in_file(task, renamed.clone(), &self.file)?;
+        Ok(renamed)
+    }
+
+    /// removes `task` from the ledger. the deletion is recorded, so that syncing does not
+    /// bring it back.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if `task` is not in the ledger.
+    pub fn delete(&self, task: &Task) -> Result<()> {
+        // tasks from before ids are recorded by the id syncing gives them.
+        let id = sync::id_in(task, &self.file)?.or_else(|| task.id.clone());
+        delete_task_in_file(task, &self.file)?;
+        match id {
+            Some(id) => sync::record_deletion(&self.file, &id, self.now()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rounding::{Rounding, RoundingMode};
+    use crate::util::testing::{temp_dir, temp_file};
+
+    #[test]
+    fn test_start_stop() {
+        let timr = Timr::new(temp_file("service_start_stop"));
+        let started = timr.start("code review", Some("0900")).unwrap();
+        assert!(timr.is_running("code review").unwrap());
+        assert_eq!(timr.list(ListFilter::Running).unwrap(), vec![started]);
+
+        let stopped = timr.stop("code review", Some("0945")).unwrap();
+        assert_eq!(stopped.time_end, Some("0945".to_string()));
+        assert_eq!(stopped.time_total, 45);
+        assert!(!timr.is_running("code review").unwrap());
+        assert_eq!(timr.list(ListFilter::Today).unwrap(), vec![stopped]);
+
+        assert!(timr.stop("code review", None).is_err());
+    }
+
+    #[test]
+    fn test_amend_and_report() {
+        let timr = Timr::new(temp_file("service_amend"));
+        timr.start("debugging", Some("0800")).unwrap();
+        let t = timr.stop("debugging", Some("0900")).unwrap();
+        timr.start("meeting", Some("0900")).unwrap();
+        timr.stop("meeting", Some("0930")).unwrap();
+
+        let amended = timr.amend(&t, Some("0730"), None).unwrap();
+        assert_eq!(amended.time_total, 90);
+        assert_eq!(timr.find("debugging", 0).unwrap(), vec![amended]);
+
+        let report = timr.report(&ListFilter::Today.into()).unwrap();
+        let totals: Vec<(&str, i64)> = report
+            .totals
+            .iter()
+            .map(|t| (t.name.as_str(), t.minutes))
+            .collect();
+        assert_eq!(totals, vec![("meeting", 30), ("debugging", 90)]);
+        assert_eq!(report.total, 120);
+        assert_eq!(report.rounded_total, 120);
+    }
+
+    #[test]
+    fn test_corrupt_ledger() {
+        let timr = Timr::new(temp_file("service_corrupt"));
+        std::fs::write(timr.file(), "{\"timr_version\":1}\nnot a task\n").unwrap();
+        assert!(timr.is_running("a").is_err());
+        assert!(timr.stop("a", None).is_err());
+        assert!(timr.switch("b", None, None).is_err());
+        assert!(timr.status().is_err());
+        assert!(timr.list(ListFilter::Running).is_err());
+        assert!(timr.find("a", 7).is_err());
+        assert!(timr.task_names(7).is_err());
+    }
+
+    #[test]
+    fn test_rename_and_delete() {
+        let timr = Timr::new(temp_file("service_rename"));
+        let t = timr.start("debuging", Some("0800")).unwrap();
+        let renamed = timr.rename(&t, " debugging ").unwrap();
+        assert_eq!(renamed.id, t.id);
+        assert_eq!(
+            timr.list(ListFilter::Running).unwrap(),
+            vec![renamed.clone()]
+        );
+        assert!(timr.rename(&renamed, "").is_err());
+
+        timr.delete(&renamed).unwrap();
+        assert!(timr.list(ListFilter::Today).unwrap().is_empty());
+        assert!(timr.delete(&renamed).is_err());
+    }
+
+    #[test]
+    fn test_report_rounding() {
+        let task = |name: &str, date: &str, minutes| {
+            Task::new(
+                date.to_string(),
+                name.to_string(),
+                "0900".to_string(),
+                Some("1000".to_string()),
+                minutes,
+            )
+        };
+        let tasks = vec![
+            task("a", "2023-10-20", 20),
+            task("b", "2023-10-20", 20),
+            task("a", "2023-10-21", 50),
+        ];
+        let mut rounding = TimecardRounding {
+            rounding: Rounding::new(15, RoundingMode::Nearest),
+            per: RoundingScope::Entry,
+        };
+
+        let report = Report::from_tasks(tasks.clone(), &rounding);
+        assert_eq!(report.totals[0].rounded, 15 + 45);
+        assert_eq!(report.days[0].rounded, 30);
+        assert_eq!(report.total, 90);
+        assert_eq!(report.rounded_total, 75);
+
+        let csv = report.to_csv(RoundingScope::Entry, &rounding, DurationStyle::Hmm);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("2023-10-20,a,0900,1000,20,15,0:20,0:15")
+        );
+
+        rounding.per = RoundingScope::Day;
+        let report = Report::from_tasks(tasks, &rounding);
+        let csv = report.to_csv(RoundingScope::Day, &rounding, DurationStyle::Iso);
+        assert_eq!(csv.lines().nth(1), Some("2023-10-20,40,45,PT40M,PT45M"));
+        assert_eq!(report.totals[0].rounded, 70);
+        assert_eq!(report.days[0].rounded, 45);
+        assert_eq!(report.rounded_total, 90);
+    }
+
+    #[test]
+    fn test_query() {
+        let timr = Timr::new(temp_file("service_query"));
+        let mut tasks = Vec::new();
+        for (date, name, minutes) in [
+            ("2023-10-20", "Code review", 30),
+            ("2023-10-21", "debugging", 90),
+            ("2023-10-22", "code cleanup", 60),
+            ("2999-01-01", "from the future", 10),
+        ] {
+            let mut t = Task::new(
+                date.to_string(),
+                name.to_string(),
+                "0900".to_string(),
+                Some("1000".to_string()),
+                minutes,
+            );
+            if minutes >= 60 {
+                t.tags = vec!["long".to_string()];
+            }
+            tasks.insert(0, t);
+        }
+        write_all_tasks(&tasks, timr.file()).unwrap();
+
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        let names = |q: &Query| -> Vec<String> {
+            timr.query(q)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.task_name)
+                .collect()
+        };
+
+        let mut q: Query = ListFilter::Between(date("2023-10-21"), date("2023-10-22")).into();
+        assert_eq!(names(&q), vec!["code cleanup", "debugging"]);
+
+        q.filter = ListFilter::Between(None, None);
+        q.name = Some("CODE".to_string());
+        q.sort = Some(Sort::Date);
+        assert_eq!(names(&q), vec!["Code review", "code cleanup"]);
+
+        q.name = None;
+        q.tag = Some("long".to_string());
+        q.sort = Some(Sort::Duration);
+        q.limit = Some(1);
+        assert_eq!(names(&q), vec!["debugging"]);
+
+        // --days does not return tasks from the future.
+        assert!(timr.list(ListFilter::Days(100000)).unwrap().len() == 3);
+    }
+
+    #[test]
+    fn test_amend_past_midnight() {
+        let config = Config {
+            timezone: Some(chrono_tz::America::New_York),
+            ..Default::default()
+        };
+        let timr = Timr::new(temp_file("service_midnight")).with_config(config);
+        let t = timr.start("night shift", Some("2300")).unwrap();
+        assert_eq!(t.started_at.unwrap().date_naive(), timr.today());
+
+        let amended = timr.amend(&t, None, Some("0100")).unwrap();
+        assert_eq!(amended.time_total, 120);
+        assert_eq!(
+            amended.ended_at.unwrap() - amended.started_at.unwrap(),
+            chrono::Duration::hours(2)
+        );
+    }
+
+    #[test]
+    fn test_amend_legacy_task() {
+        let timr = Timr::new(temp_file("service_amend_legacy"));
+        let legacy = Task::new(
+            "2023-10-6".to_string(),
+            "a".to_string(),
+            "0900".to_string(),
+            Some("1000".to_string()),
+            60,
+        );
+        output_task_to_file(legacy.clone(), timr.file()).unwrap();
+
+        let amended = timr.amend(&legacy, Some("09:30"), Some("10:15")).unwrap();
+        assert_eq!(amended.time_start, "0930");
+        assert_eq!(amended.time_end.as_deref(), Some("1015"));
+        assert_eq!(amended.time_total, 45);
+        assert_eq!(amended.started_at, None);
+        assert!(timr.amend(&amended, Some("9am"), None).is_err());
+    }
+
+    #[test]
+    fn test_stop_legacy_past_midnight() {
+        let config = Config {
+            timezone: Some(chrono_tz::Asia::Tokyo),
+            ..Default::default()
+        };
+        let timr = Timr::new(temp_file("service_stop_legacy")).with_config(config);
+        let yesterday = format_date(timr.today().pred_opt().unwrap());
+        let legacy = Task::new(yesterday, "a".to_string(), "2300".to_string(), None, 0);
+        output_task_to_file(legacy, timr.file()).unwrap();
+
+        let t = timr.stop("a", Some("0100")).unwrap();
+        assert_eq!(t.time_total, 120);
+        assert_eq!(t.ended_at, None);
+        assert_eq!(timr.find("a", 1).unwrap(), vec![t]);
+    }
+
+    #[test]
+    fn test_legacy_running_task_in_time_zone() {
+        let config = Config {
+            timezone: Some(chrono_tz::Pacific::Kiritimati),
+            ..Default::default()
+        };
+        let timr = Timr::new(temp_file("service_legacy_tz")).with_config(config);
+        let started = timr.now() - chrono::Duration::minutes(30);
+        let legacy = Task::new(
+            format_date(started.date_naive()),
+            "a".to_string(),
+            started.format("%H%M").to_string(),
+            None,
+            0,
+        );
+        output_task_to_file(legacy, timr.file()).unwrap();
+
+        let (_, elapsed) = timr.status().unwrap().running[0];
+        assert!((29..=31).contains(&elapsed));
+        assert_eq!(timr.find("a", 0).unwrap().len(), 1);
+        assert_eq!(timr.task_names(0).unwrap(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_branch_task() {
+        let dir = temp_dir("branch");
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join(".git").join("HEAD"), "ref: refs/heads/fix-login\n").unwrap();
+
+        let timr = Timr::new(temp_file("service_branch"));
+        assert!(timr.branch_task(&dir).is_err());
+
+        let mut config = Config::default();
+        config.git.enabled = true;
+        let timr = timr.with_config(config.clone());
+        let t = timr.branch_task(&dir).unwrap();
+        assert_eq!(t.task_name, "fix-login");
+        assert_eq!(t.branch.as_deref(), Some("fix-login"));
+
+        config.git.branch_as = BranchAs::Tag;
+        let timr = timr.with_config(config);
+        let t = timr.branch_task(&dir).unwrap();
+        assert_eq!(t.task_name, dir.file_name().unwrap().to_string_lossy());
+        assert!(t.has_tag("fix-login"));
+
+        timr.start_task(t, Some("0900")).unwrap();
+        timr.stop(&dir.file_name().unwrap().to_string_lossy(), Some("0945"))
+            .unwrap();
+        // started without git, so it has no branch whatever directory the tests run in.
+        Timr::new(timr.file()).start("email", Some("1000")).unwrap();
+        let report = timr.report(&ListFilter::Today.into()).unwrap();
+        assert_eq!(report.branches.len(), 1);
+        assert_eq!(report.branches[0].name, "fix-login");
+        assert_eq!(report.branches[0].minutes, 45);
+
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_continue_and_names() {
+        let timr = Timr::new(temp_file("service_continue"));
+        assert!(timr.continue_task(None).is_err());
+
+        timr.start_with_tags("writing software", Some("0900"), &["dev".to_string()])
+            .unwrap();
+        assert!(timr.continue_task(Some("writing software")).is_err());
+        timr.stop("writing software", Some("1000")).unwrap();
+        timr.start("email", Some("1000")).unwrap();
+
+        let t = timr.continue_task(None).unwrap();
+        assert_eq!(t.task_name, "writing software");
+        assert!(t.has_tag("dev") && t.time_end.is_none());
+        assert!(timr.continue_task(Some("lunch")).is_err());
+
+        assert_eq!(
+            timr.task_names(30).unwrap(),
+            vec!["writing software", "email"]
+        );
+    }
+
+    #[test]
+    fn test_templates_and_fill() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "templates": {
+                    "standup": { "task": "daily standup", "tags": ["meeting"], "minutes": 15 },
+                    "sync": { "minutes": 60 }
+                },
+                "recurring": [
+                    { "template": "standup", "days": ["mon", "tue", "wed", "thu", "fri"], "start": "0930" },
+                    { "template": "sync", "days": ["thu"], "start": "1400" }
+                ],
+                "targets": { "holidays": ["2023-10-17"] }
+            }"#,
+        )
+        .unwrap();
+        let timr = Timr::new(temp_file("service_fill")).with_config(config);
+
+        let named = |name: &str| Task::new(String::new(), name.to_string(), String::new(), None, 0);
+        let yesterday = LogTimes {
+            date: Some(timr.today() - chrono::Duration::days(1)),
+            from: Some("0930".to_string()),
+            ..Default::default()
+        };
+        let t = timr.log(named("standup"), &yesterday, false).unwrap();
+        assert_eq!(t.task_name, "daily standup");
+        assert_eq!(t.time_end.as_deref(), Some("0945"));
+        assert_eq!(t.time_total, 15);
+        assert!(t.has_tag("meeting"));
+        let now = LogTimes::default();
+        assert_eq!(timr.log(named("sync"), &now, false).unwrap().time_total, 60);
+        assert!(timr.log(named("lunch"), &now, false).is_err());
+
+        // monday to sunday, with a holiday on tuesday and a standup already logged on wednesday.
+        let monday = NaiveDate::from_ymd_opt(2023, 10, 16).unwrap();
+        let wednesday = Task::new(
+            "2023-10-18".to_string(),
+            "daily standup".to_string(),
+            "0940".to_string(),
+            Some("0950".to_string()),
+            10,
+        );
+        output_task_to_file(wednesday, timr.file()).unwrap();
+        let filled = timr
+            .fill(monday, monday + chrono::Duration::days(6))
+            .unwrap();
+        let days: Vec<(&str, &str)> = filled
+            .iter()
+            .map(|t| (t.date.as_str(), t.task_name.as_str()))
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                ("2023-10-16", "daily standup"),
+                ("2023-10-19", "daily standup"),
+                ("2023-10-19", "sync"),
+                ("2023-10-20", "daily standup"),
+            ]
+        );
+        assert!(timr
+            .fill(monday, monday + chrono::Duration::days(6))
+            .unwrap()
+            .is_empty());
+
+        // nothing is logged if one of the days has something else in the way.
+        let dentist = Task::new(
+            "2023-10-24".to_string(),
+            "dentist".to_string(),
+            "0900".to_string(),
+            Some("1000".to_string()),
+            60,
+        );
+        output_task_to_file(dentist, timr.file()).unwrap();
+        let next_monday = monday + chrono::Duration::days(7);
+        assert!(timr
+            .fill(next_monday, next_monday + chrono::Duration::days(6))
+            .is_err());
+        assert!(read_all_tasks(timr.file())
+            .unwrap()
+            .iter()
+            .all(|t| t.date != "2023-10-23"));
+
+        // days that have not happened yet are left for later.
+        let tomorrow = timr.today() + chrono::Duration::days(1);
+        assert!(timr
+            .fill(tomorrow, tomorrow + chrono::Duration::days(6))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_log() {
+        let timr = Timr::new(temp_file("service_log"));
+        let task = Task::new(String::new(), "forgot".to_string(), String::new(), None, 0);
+        let date = NaiveDate::from_ymd_opt(2023, 10, 20);
+        let times = |from: Option<&str>, to: Option<&str>, minutes: Option<i64>| LogTimes {
+            date,
+            from: from.map(|s| s.to_string()),
+            to: to.map(|s| s.to_string()),
+            minutes,
+        };
+
+        let t = timr
+            .log(
+                task.clone(),
+                &times(Some("0900"), Some("1130"), None),
+                false,
+            )
+            .unwrap();
+        assert_eq!(t.date, "2023-10-20");
+        assert_eq!(t.time_total, 150);
+
+        let t = timr
+            .log(task.clone(), &times(None, Some("1300"), Some(60)), false)
+            .unwrap();
+        assert_eq!(t.time_start, "1200");
+
+        // past midnight.
+        let t = timr
+            .log(
+                task.clone(),
+                &times(Some("2300"), Some("0100"), None),
+                false,
+            )
+            .unwrap();
+        assert_eq!(t.time_total, 120);
+
+        let overlap = times(Some("1100"), None, Some(30));
+        let err = timr.log(task.clone(), &overlap, false).unwrap_err();
+        assert!(err.to_string().contains("overlaps forgot"));
+        assert!(timr.log(task.clone(), &overlap, true).is_ok());
+        // the overnight task runs into the next day.
+        let early = LogTimes {
+            date: NaiveDate::from_ymd_opt(2023, 10, 21),
+            ..times(Some("0030"), Some("0200"), None)
+        };
+        assert!(timr.log(task.clone(), &early, false).is_err());
+
+        assert!(timr
+            .log(task.clone(), &times(Some("0900"), None, None), false)
+            .is_err());
+        assert!(timr
+            .log(task.clone(), &times(None, None, Some(30)), false)
+            .is_err());
+        assert!(timr
+            .log(
+                task.clone(),
+                &times(Some("0600"), Some("0700"), Some(60)),
+                false
+            )
+            .is_err());
+        let tomorrow = LogTimes {
+            date: Some(timr.today() + chrono::Duration::days(1)),
+            ..times(Some("0900"), Some("1000"), None)
+        };
+        assert!(timr.log(task, &tomorrow, false).is_err());
+    }
+
+    #[test]
+    fn test_switch() {
+        let timr = Timr::new(temp_file("service_switch"));
+        timr.start("a", Some("0800")).unwrap();
+        timr.start("b", Some("0830")).unwrap();
+
+        let (ended, started) = timr.switch("c", Some("a"), Some("0900")).unwrap();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].time_end, Some("0900".to_string()));
+        assert_eq!(ended[0].time_total, 60);
+        assert_eq!(started.time_start, "0900");
+        assert!(timr.is_running("b").unwrap() && timr.is_running("c").unwrap());
+
+        let (ended, _) = timr.switch("d", None, Some("1000")).unwrap();
+        let names: Vec<&str> = ended.iter().map(|t| t.task_name.as_str()).collect();
+        assert_eq!(names, vec!["c", "b"]);
+        assert_eq!(timr.list(ListFilter::Running).unwrap().len(), 1);
+
+        assert!(timr.switch("e", Some("a"), None).is_err());
+        // a mistyped time before the start is refused rather than logged as negative.
+        assert!(timr.stop("d", Some("0930")).is_err());
+        assert!(timr.switch("e", None, Some("0930")).is_err());
+        assert!(timr.is_running("d").unwrap());
+    }
+
+    #[test]
+    fn test_invoice() {
+        let mut config = Config::default();
+        config.billing.default_rate = Some(60.0);
+        let timr = Timr::new(temp_file("service_invoice")).with_config(config);
+        timr.start("debugging", Some("0800")).unwrap();
+        timr.stop("debugging", Some("0930")).unwrap();
+        timr.start("running", Some("0930")).unwrap();
+
+        let today = timr.today();
+        let preview = timr.invoice(None, today, true).unwrap();
+        assert_eq!(preview.total, 90.0);
+
+        let invoice = timr.invoice(Some(today), today, false).unwrap();
+        assert_eq!(invoice.number, "INV-0001");
+        assert_eq!(invoice.tasks.len(), 1);
+        assert_eq!(
+            timr.find("debugging", 0).unwrap()[0].invoice,
+            Some("INV-0001".to_string())
+        );
+
+        // the same hours are not billed twice.
+        assert!(timr.invoice(None, today, false).is_err());
+    }
+
+    #[test]
+    fn test_status() {
+        let timr = Timr::new(temp_file("service_status"));
+        timr.start("logged", Some("0000")).unwrap();
+        timr.stop("logged", Some("0010")).unwrap();
+        timr.start("running", None).unwrap();
+
+        let status = timr.status().unwrap();
+        assert_eq!(status.running.len(), 1);
+        assert_eq!(status.running[0].0.task_name, "running");
+        assert!(status.running[0].1 <= 1);
+        assert!(status.today_total >= 10 && status.today_total <= 11);
+        assert_eq!(status.week_total, status.today_total);
+        assert_eq!(status.today_target, 0);
+    }
+
+    #[test]
+    fn test_balance() {
+        let mut config = Config::default();
+        config.targets.weekly = Some(0.0);
+        let timr = Timr::new(temp_file("service_balance")).with_config(config);
+        timr.start("logged", Some("0000")).unwrap();
+        timr.stop("logged", Some("0030")).unwrap();
+
+        let balance = timr.balance().unwrap();
+        assert_eq!(balance.weeks.len(), 1);
+        assert_eq!(balance.balance, 30);
+    }
+}
@@ -0,0 +1,641 @@
+use super::{
+    archive,
+    encryption::{self, set_secret},
+    events::TaskKey,
+    schema::{self, Header},
+    service::Timr,
+    tasks::Task,
+    utility::*,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Sync settings, the `sync` section of the config.
+///
+/// # Example
+/// ```json
+/// "sync": { "remote": "~/Dropbox/timr" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// the directory or ledger `timr sync` uses when none is given.
+    pub remote: Option<String>,
+    /// `remote` is a git repository to clone and push to, rather than a directory.
+    pub git: bool,
+}
+
+/// Where the other copy of the ledger is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remote {
+    /// a ledger, or a directory with a ledger of the same name in it, e.g. a synced folder.
+    Dir(PathBuf),
+    /// a git repository with the ledger at its root. it can be a path to a local bare repository.
+    Git(String),
+}
+
+impl Remote {
+    /// the remote to sync with: `remote` if given, or else the one in the config.
+    pub fn of(remote: Option<&str>, git: bool, config: &SyncConfig) -> Result<Self> {
+        let (remote, git) = match remote {
+            Some(r) => (r.to_string(), git),
+            None => match &config.remote {
+                Some(r) => (r.to_string(), git || config.git),
+                None => bail!("give a directory to sync with, or set `remote` in the `sync` section of the config"),
+            },
+        };
+        Ok(match git {
+            true => Remote::Git(remote),
+            false => Remote::Dir(super::hook::expand_home(&remote)),
+        })
+    }
+}
+
+/// What [`sync`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncReport {
+    /// tasks added, changed, or deleted in this ledger.
+    pub pulled: usize,
+    /// tasks added, changed, or deleted in the other one.
+    pub pushed: usize,
+    /// tasks changed differently on both sides since the last sync.
+    pub conflicts: usize,
+    /// tasks in the ledger after syncing.
+    pub tasks: usize,
+}
+
+/// What sync keeps next to a ledger, in `<ledger>.sync.json`. it goes wherever the ledger
+/// goes, so that a deletion reaches every copy.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SyncState {
+    /// ids of the tasks deleted from the ledger, and when they were deleted.
+    pub deleted: BTreeMap<String, DateTime<FixedOffset>>,
+    /// when the ledger was last synced with each remote.
+    pub synced: BTreeMap<String, DateTime<FixedOffset>>,
+}
+
+impl SyncState {
+    /// the file the state of the ledger `file` is kept in, e.g. `timr.sync.json`.
+    pub fn path(file: &str) -> PathBuf {
+        Path::new(file).with_extension("sync.json")
+    }
+
+    /// the state of the ledger `file`. empty if it has none yet.
+    pub fn read(file: &str) -> Result<Self> {
+        let path = Self::path(file);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .with_context(|| format!("error reading {}", path.display()))
+    }
+
+    pub fn write(&self, file: &str) -> Result<()> {
+        Ok(std::fs::write(
+            Self::path(file),
+            serde_json::to_string(self)? + "\n",
+        )?)
+    }
+}
+
+/// records that the task `id` was deleted from the ledger `file` at `now`,
+/// so that syncing does not bring it back.
+pub fn record_deletion(file: &str, id: &str, now: DateTime<FixedOffset>) -> Result<()> {
+    let mut state = SyncState::read(file)?;
+    state.deleted.insert(id.to_string(), now);
+    state.write(file)
+}
+
+/// what a task is matched by across ledgers: its id, or its key if it was written
+/// before tasks had ids.
+pub fn identity(t: &Task) -> String {
+    match &t.id {
+        Some(id) => id.clone(),
+        None => format!("{}@{} {}", t.task_name, t.date, t.time_start),
+    }
+}
+
+/// the 64-bit FNV-1a hash of `s`. unlike std's hasher, it is the same in every build.
+fn fnv(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// gives the tasks written before tasks had ids one, made from their key so that a task
+/// gets the same id in every copy of the ledger. identical tasks are told apart by their order.
+pub fn assign_ids(tasks: &mut [Task]) {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for t in tasks.iter_mut().filter(|t| t.id.is_none()) {
+        let key = identity(t);
+        let n = seen.entry(key.clone()).or_default();
+        t.id = Some(format!("{:016x}", fnv(&format!("{}#{}", key, n))));
+        *n += 1;
+    }
+}
+
+/// which of two versions of a task is kept: the one changed last. a version with no
+/// time wins over neither, and a tie is broken by comparing them, so that both sides
+/// pick the same one.
+fn newer<'a>(a: &'a Task, b: &'a Task) -> &'a Task {
+    let json = |t: &Task| serde_json::to_string(t).unwrap_or_default();
+    match (a.modified_at, b.modified_at) {
+        (Some(x), Some(y)) if x != y => match x > y {
+            true => a,
+            false => b,
+        },
+        (Some(_), None) => a,
+        (None, Some(_)) => b,
+        _ => match json(a) >= json(b) {
+            true => a,
+            false => b,
+        },
+    }
+}
+
+/// merges two ledgers' tasks, matched by [`identity`]. tasks in `deleted` are dropped,
+/// unless they were changed after they were deleted.
+///
+/// returns the merged tasks, newest first, and the number of tasks that were changed
+/// differently on both sides since `since`, the last sync.
+pub fn merge(
+    local: &[Task],
+    remote: &[Task],
+    deleted: &BTreeMap<String, DateTime<FixedOffset>>,
+    since: Option<DateTime<FixedOffset>>,
+) -> (Vec<Task>, usize) {
+    // without a time, a task may have changed at any point.
+    let changed = |t: &Task| match (since, t.modified_at) {
+        (Some(since), Some(m)) => m > since,
+        _ => true,
+    };
+    let mut merged: BTreeMap<String, Task> = BTreeMap::new();
+    let mut conflicts = 0;
+    for t in local.iter().chain(remote) {
+        match merged.get(&identity(t)) {
+            Some(m) if m == t => (),
+            Some(m) => {
+                if changed(m) && changed(t) {
+                    conflicts += 1;
+                }
+                let keep = newer(m, t).clone();
+                merged.insert(identity(t), keep);
+            }
+            None => {
+                merged.insert(identity(t), t.clone());
+            }
+        }
+    }
+    merged.retain(|id, t| match deleted.get(id) {
+        Some(at) => t.modified_at.is_some_and(|m| m > *at),
+        None => true,
+    });
+    let mut tasks: Vec<Task> = merged.into_values().collect();
+    // the same order on both sides, whatever order the tasks were written in.
+    tasks.sort_by_key(|t| {
+        let start = parse_time(&t.time_start).ok();
+        Reverse((t.naive_date(), start, identity(t)))
+    });
+    (tasks, conflicts)
+}
+
+/// the tasks of `merged` that belong in the ledger `file`: those it has not archived.
+fn for_ledger(merged: &[Task], file: &str) -> Result<Vec<Task>> {
+    let mut archived = archive::read_archived(None, NaiveDate::MAX, file)?;
+    assign_ids(&mut archived);
+    let archived: BTreeSet<String> = archived.iter().map(identity).collect();
+    Ok(merged
+        .iter()
+        .filter(|t| !archived.contains(&identity(t)))
+        .cloned()
+        .collect())
+}
+
+/// the number of tasks in `to` that are not in `from` as they are,
+/// and of those in `from` that are gone from `to`.
+fn changes(from: &[Task], to: &[Task]) -> usize {
+    let gone = from
+        .iter()
+        .filter(|t| !to.iter().any(|n| identity(n) == identity(t)));
+    to.iter().filter(|t| !from.contains(t)).count() + gone.count()
+}
+
+/// the id `task` is synced by in the ledger `file`: its own, or the one [`assign_ids`]
+/// gives it if it has none. `None` if it is not in the ledger.
+pub fn id_in(task: &Task, file: &str) -> Result<Option<String>> {
+    let (tasks, with_ids) = read_with_ids(file)?;
+    let key = TaskKey::of(task);
+    Ok(tasks
+        .iter()
+        .position(|t| key.matches(t))
+        .and_then(|i| with_ids[i].id.clone()))
+}
+
+/// the tasks in the ledger `file` as it is, and with ids given to those without one.
+fn read_with_ids(file: &str) -> Result<(Vec<Task>, Vec<Task>)> {
+    let tasks = read_all_tasks(file)?;
+    let mut with_ids = tasks.clone();
+    assign_ids(&mut with_ids);
+    Ok((tasks, with_ids))
+}
+
+/// merges the ledger of `timr` with `remote`, and writes the result to both. `name` is what
+/// the remote is known by, to remember when it was last synced with.
+/// a ledger is only written if it changed, and tasks it has archived are left in its archive.
+///
+/// a new remote ledger is encrypted like the local one. either is unlocked with the other's
+/// secret if none is given for it.
+pub fn sync_files(timr: &Timr, remote: &str, name: &str) -> Result<SyncReport> {
+    let local = timr.file();
+    match (
+        encryption::secret_for(local),
+        encryption::secret_for(remote),
+    ) {
+        (Some(s), None) => set_secret(remote, s),
+        (None, Some(s)) => set_secret(local, s),
+        _ => (),
+    }
+    let (read_ours, ours) = read_with_ids(local)?;
+    let (read_theirs, theirs) = read_with_ids(remote)?;
+    let mut our_state = SyncState::read(local)?;
+    let mut their_state = SyncState::read(remote)?;
+    let mut deleted = our_state.deleted.clone();
+    for (id, at) in &their_state.deleted {
+        let d = deleted.entry(id.clone()).or_insert(*at);
+        *d = (*d).max(*at);
+    }
+    let (merged, conflicts) = merge(
+        &ours,
+        &theirs,
+        &deleted,
+        our_state.synced.get(name).copied(),
+    );
+
+    let new_ours = for_ledger(&merged, local)?;
+    let new_theirs = for_ledger(&merged, remote)?;
+    if new_ours != read_ours {
+        write_all_tasks(&new_ours, local)?;
+    }
+    if !Path::new(remote).exists() {
+        let header = Header {
+            encryption: schema::header_of(local)?.and_then(|h| h.encryption),
+            ..Header::current()
+        };
+        write_ledger(&new_theirs, &header, remote)?;
+    } else if new_theirs != read_theirs {
+        write_all_tasks(&new_theirs, remote)?;
+    }
+    if their_state.deleted != deleted {
+        their_state.deleted = deleted.clone();
+        their_state.write(remote)?;
+    }
+    our_state.deleted = deleted;
+    our_state.synced.insert(name.to_string(), timr.now());
+    our_state.write(local)?;
+    Ok(SyncReport {
+        pulled: changes(&ours, &new_ours),
+        pushed: changes(&theirs, &new_theirs),
+        conflicts,
+        tasks: new_ours.len(),
+    })
+}
+
+/// the ledger in `dir` with the same name as `file`, or `dir` itself if it is not a directory.
+fn ledger_in(dir: &Path, file: &str) -> PathBuf {
+    match (dir.is_dir(), Path::new(file).file_name()) {
+        (true, Some(name)) => dir.join(name),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// runs git with `args` in `dir`, returning what it printed.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("could not run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// syncs the ledger of `timr` with the copy in the git repository `url`: the repository is
+/// cloned, the ledgers are merged, and the result is committed and pushed back.
+fn sync_git(timr: &Timr, url: &str) -> Result<SyncReport> {
+    let work = std::env::temp_dir().join(format!("timr_sync_{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&work);
+    let cloned = Command::new("git")
+        .args(["clone", "--quiet", url])
+        .arg(&work)
+        .output()
+        .context("could not run git")?;
+    if !cloned.status.success() {
+        bail!(
+            "could not clone {}: {}",
+            url,
+            String::from_utf8_lossy(&cloned.stderr).trim()
+        );
+    }
+
+    let result = (|| {
+        let remote = ledger_in(&work, timr.file()).to_string_lossy().to_string();
+        let report = sync_files(timr, &remote, url)?;
+        let files: Vec<String> = [PathBuf::from(&remote), SyncState::path(&remote)]
+            .iter()
+            .filter(|p| p.exists())
+            .filter_map(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect();
+        let mut status = vec!["status", "--porcelain"];
+        status.extend(files.iter().map(String::as_str));
+        if git(&work, &status)?.trim().is_empty() {
+            return Ok(report);
+        }
+        let mut add = vec!["add"];
+        add.extend(files.iter().map(String::as_str));
+        git(&work, &add)?;
+        let message = format!("timr sync: {} task(s) added or changed", report.pushed);
+        // commit as timr when git does not know who the user is.
+        match git(&work, &["config", "user.email"]) {
+            std::result::Result::Ok(_) => git(&work, &["commit", "--quiet", "-m", &message])?,
+            Err(_) => git(
+                &work,
+                &[
+                    "-c",
+                    "user.name=timr",
+                    "-c",
+                    "user.email=timr@localhost",
+                    "commit",
+                    "--quiet",
+                    "-m",
+                    &message,
+                ],
+            )?,
+        };
+        git(&work, &["push", "--quiet", "origin", "HEAD"])
+            .context("the remote changed while syncing. run timr sync again")?;
+        Ok(report)
+    })();
+    _ = std::fs::remove_dir_all(&work);
+    result
+}
+
+/// merges the ledger with the one at `remote`, so both end up with the same tasks.
+/// tasks are matched by id, and a task changed on both sides keeps the version changed last.
+/// tasks deleted on either side are deleted on both.
+///
+/// # Errors
+///
+/// returns an error if either ledger cannot be read or written, or git fails.
+pub fn sync(timr: &Timr, remote: &Remote) -> Result<SyncReport> {
+    match remote {
+        Remote::Dir(dir) => {
+            let remote = ledger_in(dir, timr.file());
+            // a ledger that does not exist yet is only made if it is named like one.
+            if !remote.exists() && remote.extension().is_none_or(|e| e != "json") {
+                bail!("there is no directory {}", dir.display());
+            }
+            if Path::new(timr.file()).canonicalize().ok() == remote.canonicalize().ok()
+                && remote.exists()
+            {
+                bail!("{} is the ledger itself", remote.display());
+            }
+            let remote = remote.to_string_lossy();
+            sync_files(timr, &remote, &remote)
+        }
+        Remote::Git(url) => sync_git(timr, url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::testing::temp_dir;
+    use chrono::{DateTime, Duration};
+
+    fn at(s: &str) -> DateTime<chrono::FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = Task::new(
+            "2023-10-6".into(),
+            "review".into(),
+            "0900".into(),
+            Some("1000".into()),
+            60,
+        );
+        a.id = Some("a".into());
+        let mut b = Task::new("2023-10-7".into(), "email".into(), "0800".into(), None, 0);
+        b.id = Some("b".into());
+        let legacy = Task::new(
+            "2023-10-5".into(),
+            "deploy".into(),
+            "1300".into(),
+            Some("1400".into()),
+            60,
+        );
+
+        // the same task renamed on both machines, at different times.
+        let mut ours = a.clone();
+        ours.task_name = "code review".into();
+        ours.modified_at = Some(at("2023-10-06T10:05:00+02:00"));
+        let mut theirs = a.clone();
+        theirs.task_name = "pairing".into();
+        theirs.modified_at = Some(at("2023-10-06T09:35:00+01:00"));
+
+        let none = BTreeMap::new();
+        let (merged, conflicts) = merge(
+            &[ours.clone(), legacy.clone()],
+            &[b.clone(), theirs.clone(), legacy.clone()],
+            &none,
+            None,
+        );
+        assert_eq!(conflicts, 1);
+        assert_eq!(merged, vec![b.clone(), theirs.clone(), legacy.clone()]);
+        let (reversed, _) = merge(
+            &[b.clone(), theirs.clone(), legacy.clone()],
+            &[ours.clone(), legacy.clone()],
+            &none,
+            None,
+        );
+        assert_eq!(reversed, merged);
+
+        // only one side changed since the last sync, so there is nothing to resolve.
+        let since = Some(at("2023-10-06T08:20:00Z"));
+        assert_eq!(merge(&[ours.clone()], &[theirs.clone()], &none, since).1, 0);
+
+        // a deleted task stays deleted, unless it was changed after that.
+        let deleted = BTreeMap::from([("a".to_string(), at("2023-10-06T08:20:00Z"))]);
+        assert_eq!(
+            merge(&[ours.clone()], &[b.clone()], &deleted, None).0,
+            vec![b.clone()]
+        );
+        let (kept, _) = merge(&[theirs.clone()], &[b.clone()], &deleted, None);
+        assert_eq!(kept, vec![b.clone(), theirs.clone()]);
+
+        // with the same time, both sides still keep the same version.
+        let mut x = a.clone();
+        x.time_end = Some("1030".into());
+        assert_eq!(
+            merge(&[a.clone()], &[x.clone()], &none, None).0,
+            merge(&[x], &[a], &none, None).0
+        );
+    }
+
+    #[test]
+    fn test_assign_ids() {
+        // the same task logged twice by mistake, before tasks had ids.
+        let debugging = Task::new(
+            "2023-10-6".into(),
+            "debugging".into(),
+            "1230".into(),
+            Some("1300".into()),
+            30,
+        );
+        let mut ours = vec![debugging.clone(), debugging.clone()];
+        let mut theirs = ours.clone();
+        assign_ids(&mut ours);
+        assign_ids(&mut theirs);
+        assert_ne!(ours[0].id, ours[1].id);
+        assert_eq!(ours, theirs);
+        assert_eq!(merge(&ours, &theirs, &BTreeMap::new(), None).0.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_dirs() {
+        let laptop = temp_dir("sync_laptop");
+        let desktop = temp_dir("sync_desktop");
+        let l = Timr::new(laptop.join("timr.json").to_string_lossy());
+        let d = Timr::new(desktop.join("timr.json").to_string_lossy());
+
+        let now = l.now();
+        l.log_task(
+            Task::new_task_today(),
+            now - Duration::hours(3),
+            now - Duration::hours(2),
+        )
+        .unwrap();
+        let running = d.start("writing", None).unwrap();
+
+        let report = sync(&l, &Remote::Dir(desktop.clone())).unwrap();
+        assert_eq!(
+            (report.pulled, report.pushed, report.conflicts, report.tasks),
+            (1, 1, 0, 2)
+        );
+        assert_eq!(
+            read_all_tasks(l.file()).unwrap(),
+            read_all_tasks(d.file()).unwrap()
+        );
+
+        // stopping on one machine and syncing from the other brings the change over.
+        let stopped = d.stop("writing", None).unwrap();
+        let report = sync(&l, &Remote::Dir(desktop.clone())).unwrap();
+        assert_eq!((report.pulled, report.pushed, report.conflicts), (1, 0, 0));
+        let tasks = read_all_tasks(l.file()).unwrap();
+        assert!(tasks.contains(&stopped) && !tasks.contains(&running));
+        assert_eq!(
+            sync(&l, &Remote::Dir(desktop.clone())).unwrap(),
+            SyncReport {
+                tasks: 2,
+                ..Default::default()
+            }
+        );
+
+        // a task deleted on one side is deleted on the other, and does not come back.
+        l.delete(&stopped).unwrap();
+        let report = sync(&l, &Remote::Dir(desktop.clone())).unwrap();
+        assert_eq!((report.pulled, report.pushed, report.tasks), (0, 1, 1));
+        assert!(!read_all_tasks(d.file()).unwrap().contains(&stopped));
+        assert_eq!(sync(&l, &Remote::Dir(desktop.clone())).unwrap().tasks, 1);
+        assert!(SyncState::read(d.file())
+            .unwrap()
+            .deleted
+            .contains_key(stopped.id.as_ref().unwrap()));
+
+        _ = std::fs::remove_dir_all(&laptop);
+        _ = std::fs::remove_dir_all(&desktop);
+    }
+
+    #[test]
+    fn test_delete_legacy_then_sync() {
+        let laptop = temp_dir("sync_legacy_laptop");
+        let desktop = temp_dir("sync_legacy_desktop");
+        let l = Timr::new(laptop.join("timr.json").to_string_lossy());
+        let d = Timr::new(desktop.join("timr.json").to_string_lossy());
+
+        // the same ledger on both machines, from before tasks had ids.
+        let legacy = |name: &str| {
+            Task::new(
+                "2023-10-6".into(),
+                name.into(),
+                "0900".into(),
+                Some("1000".into()),
+                60,
+            )
+        };
+        for file in [l.file(), d.file()] {
+            write_all_tasks(&[legacy("email"), legacy("review")], file).unwrap();
+        }
+
+        l.delete(&legacy("email")).unwrap();
+        sync(&l, &Remote::Dir(desktop.clone())).unwrap();
+        for file in [l.file(), d.file()] {
+            let names: Vec<String> = read_all_tasks(file)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.task_name)
+                .collect();
+            assert_eq!(names, vec!["review"]);
+        }
+
+        _ = std::fs::remove_dir_all(&laptop);
+        _ = std::fs::remove_dir_all(&desktop);
+    }
+
+    #[test]
+    fn test_sync_git() {
+        let root = temp_dir("sync_git");
+        let bare = root.join("ledger.git");
+        let init = Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(&bare)
+            .status();
+        if !init.is_ok_and(|s| s.success()) {
+            // git is not installed.
+            return;
+        }
+        let remote = Remote::Git(bare.to_string_lossy().to_string());
+        for dir in ["laptop", "desktop"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let l = Timr::new(root.join("laptop").join("timr.json").to_string_lossy());
+        let d = Timr::new(root.join("desktop").join("timr.json").to_string_lossy());
+
+        let writing = l.start("writing", None).unwrap();
+        assert_eq!(sync(&l, &remote).unwrap().pushed, 1);
+        let email = d.start("email", None).unwrap();
+        let report = sync(&d, &remote).unwrap();
+        assert_eq!((report.pulled, report.pushed), (1, 1));
+        assert_eq!(sync(&l, &remote).unwrap().pulled, 1);
+
+        let tasks = read_all_tasks(l.file()).unwrap();
+        assert!(tasks.contains(&writing) && tasks.contains(&email));
+        assert_eq!(tasks, read_all_tasks(d.file()).unwrap());
+        _ = std::fs::remove_dir_all(&root);
+    }
+}
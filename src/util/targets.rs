@@ -0,0 +1,199 @@
+use super::{tasks::Task, utility::week_start};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Hours expected to be worked, the `targets` section of the config.
+///
+/// # Example
+/// ```json
+/// "targets": { "daily": { "mon": 8, "tue": 8, "wed": 8, "thu": 8, "fri": 8 }, "holidays": ["2023-12-25"] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Targets {
+    /// hours expected on each weekday, keyed by name ("mon" or "monday").
+    /// days that are not listed expect nothing.
+    pub daily: BTreeMap<String, f64>,
+    /// hours expected per week. with no daily targets, this is spread over monday to friday.
+    /// defaults to the sum of the daily targets.
+    pub weekly: Option<f64>,
+    /// days off, which expect nothing.
+    pub holidays: Vec<NaiveDate>,
+    /// first day counted in the balance. defaults to the first day in the ledger.
+    pub since: Option<NaiveDate>,
+}
+
+fn hours_to_minutes(hours: f64) -> i64 {
+    (hours * 60.0).round() as i64
+}
+
+impl Targets {
+    /// `true` if any target has been configured.
+    pub fn is_set(&self) -> bool {
+        !self.daily.is_empty() || self.weekly.is_some()
+    }
+
+    /// minutes expected on `date`.
+    pub fn daily_minutes(&self, date: NaiveDate) -> i64 {
+        match self.holidays.contains(&date) {
+            true => 0,
+            false => self.weekday_minutes(date),
+        }
+    }
+
+    /// minutes expected on `date`'s weekday, whether or not it is a holiday.
+    fn weekday_minutes(&self, date: NaiveDate) -> i64 {
+        if self.daily.is_empty() {
+            return match (self.weekly, date.weekday()) {
+                (_, Weekday::Sat | Weekday::Sun) => 0,
+                (Some(weekly), _) => hours_to_minutes(weekly / 5.0),
+                (None, _) => 0,
+            };
+        }
+        self.daily
+            .iter()
+            .find(|(day, _)| day.parse::<Weekday>().ok() == Some(date.weekday()))
+            .map_or(0, |(_, hours)| hours_to_minutes(*hours))
+    }
+
+    /// minutes expected in the week starting on `start`, less any holidays.
+    pub fn weekly_minutes(&self, start: NaiveDate) -> i64 {
+        let days = (0..7).map(|i| start + Duration::days(i));
+        match (self.weekly, self.daily.is_empty()) {
+            (Some(weekly), false) => {
+                let holidays: i64 = days
+                    .filter(|d| self.holidays.contains(d))
+                    .map(|d| self.weekday_minutes(d))
+                    .sum();
+                hours_to_minutes(weekly) - holidays
+            }
+            _ => days.map(|d| self.daily_minutes(d)).sum(),
+        }
+    }
+
+    /// worked and expected minutes for each week from `since` up to and including `today`,
+    /// from the ended tasks in `tasks`. only days up to `today` count toward the current week.
+    /// weeks begin on `first_day`.
+    pub fn balance(
+        &self,
+        tasks: &[Task],
+        since: NaiveDate,
+        today: NaiveDate,
+        first_day: Weekday,
+    ) -> Balance {
+        let mut weeks: Vec<WeekBalance> = Vec::new();
+        let mut day = since;
+        while day <= today {
+            let start = week_start(day, first_day);
+            if weeks.last().is_none_or(|w| w.week_start != start) {
+                weeks.push(WeekBalance {
+                    week_start: start,
+                    ..Default::default()
+                });
+            }
+            let week = weeks.last_mut().unwrap();
+            week.target += self.daily_minutes(day);
+            week.worked += tasks
+                .iter()
+                .filter(|t| t.time_end.is_some() && t.naive_date() == Some(day))
+                .map(|t| t.time_total)
+                .sum::<i64>();
+            day += Duration::days(1);
+        }
+        let balance = weeks.iter().map(|w| w.worked - w.target).sum();
+        Balance { weeks, balance }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct WeekBalance {
+    pub week_start: NaiveDate,
+    /// minutes worked.
+    pub worked: i64,
+    /// minutes expected.
+    pub target: i64,
+}
+
+/// Overtime (or undertime) across weeks, in minutes.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Balance {
+    pub weeks: Vec<WeekBalance>,
+    /// worked less expected across every week. positive is overtime.
+    pub balance: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn targets() -> Targets {
+        let mut daily = BTreeMap::new();
+        for day in ["mon", "tue", "wed", "thu"] {
+            daily.insert(day.to_string(), 8.0);
+        }
+        daily.insert("friday".to_string(), 6.5);
+        Targets {
+            daily,
+            holidays: vec![date("2023-10-20")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daily_and_weekly() {
+        let t = targets();
+        assert_eq!(t.daily_minutes(date("2023-10-16")), 480);
+        assert_eq!(t.daily_minutes(date("2023-10-27")), 390);
+        assert_eq!(t.daily_minutes(date("2023-10-20")), 0);
+        assert_eq!(t.daily_minutes(date("2023-10-21")), 0);
+        assert_eq!(t.weekly_minutes(date("2023-10-16")), 4 * 480);
+        assert_eq!(t.weekly_minutes(date("2023-10-23")), 4 * 480 + 390);
+
+        let weekly = Targets {
+            weekly: Some(40.0),
+            ..Default::default()
+        };
+        assert_eq!(weekly.daily_minutes(date("2023-10-16")), 480);
+        assert_eq!(weekly.weekly_minutes(date("2023-10-16")), 2400);
+
+        let both = Targets {
+            weekly: Some(38.0),
+            ..targets()
+        };
+        assert_eq!(both.weekly_minutes(date("2023-10-16")), 38 * 60 - 390);
+    }
+
+    #[test]
+    fn test_balance() {
+        let task = |d: &str, minutes| {
+            Task::new(
+                d.to_string(),
+                "work".to_string(),
+                "0900".to_string(),
+                Some("1700".to_string()),
+                minutes,
+            )
+        };
+        let tasks = vec![
+            task("2023-10-16", 540),
+            task("2023-10-17", 480),
+            task("2023-10-23", 420),
+        ];
+        let b = targets().balance(&tasks, date("2023-10-16"), date("2023-10-23"), Weekday::Mon);
+        assert_eq!(b.weeks.len(), 2);
+        assert_eq!(b.weeks[0].worked, 1020);
+        assert_eq!(b.weeks[0].target, 4 * 480);
+        assert_eq!(b.weeks[1].target, 480);
+        assert_eq!(b.balance, 1020 - 1920 + 420 - 480);
+
+        // with weeks starting on sunday, monday the 23rd is in the second week as well.
+        let b = targets().balance(&tasks, date("2023-10-15"), date("2023-10-23"), Weekday::Sun);
+        assert_eq!(b.weeks.len(), 2);
+        assert_eq!(b.weeks[1].week_start, date("2023-10-22"));
+    }
+}
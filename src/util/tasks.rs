@@ -0,0 +1,190 @@
+use super::duration::{Duration, DurationStyle};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize, Default, Clone)]
+pub struct Task {
+    pub date: String,
+    pub task_name: String,
+    pub time_start: String,
+    pub time_end: Option<String>,
+    pub time_total: i64,
+    /// labels used to group tasks, e.g. `pomodoro`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// project the task is billed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// number of the invoice the task was billed on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+    /// when the task started, with the utc offset it started in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<FixedOffset>>,
+    /// when the task ended, with the utc offset it ended in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<FixedOffset>>,
+    /// git branch checked out when the task started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// git repository the task was started in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// identifies the task on every machine its ledger is synced to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// when the task was last changed, which decides between two versions of it when syncing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<FixedOffset>>,
+}
+
+impl Task {
+    pub fn new(
+        date: String,
+        task_name: String,
+        time_start: String,
+        time_end: Option<String>,
+        time_total: i64,
+    ) -> Self {
+        Self {
+            date,
+            task_name,
+            time_start,
+            time_end,
+            time_total,
+            tags: Vec::new(),
+            project: None,
+            invoice: None,
+            started_at: None,
+            ended_at: None,
+            branch: None,
+            repo: None,
+            id: None,
+            modified_at: None,
+        }
+    }
+
+    pub fn new_task_today() -> Self {
+        // create a temp task for today
+        let date = chrono::Local::now();
+        let date_s = format!("{}-{}-{}", date.year(), date.month(), date.day());
+        Self {
+            date: date_s,
+            ..Default::default()
+        }
+    }
+    pub fn _new_task_from_date(date: NaiveDate) -> Self {
+        // create a temp task for a specified date.
+        Self {
+            date: date.to_string(),
+            ..Default::default()
+        }
+    }
+    /// parses a task from a line of the ledger.
+    ///
+    /// # Errors
+    ///
+    /// returns an error if `s` is not a task.
+    pub fn task_from_string(s: String) -> Result<Self> {
+        serde_json::from_str(s.as_str()).map_err(|e| anyhow!("error parsing task from json: {e}"))
+    }
+
+    /// marks the task as changed at `now`, giving it an id if it does not have one yet.
+    pub fn touch(&mut self, now: DateTime<FixedOffset>) {
+        if self.id.is_none() {
+            self.id = Some(format!("{:016x}", rand::random::<u64>()));
+        }
+        self.modified_at = Some(now);
+    }
+
+    pub fn to_json_string(&self) -> String {
+        let mut s = serde_json::to_string(self).unwrap();
+        s.insert(s.len(), '\n');
+        s
+    }
+
+    /// the date the task was started on, if it can be parsed.
+    pub fn naive_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()
+    }
+
+    /// minutes from start to end, from the timestamps. `None` unless both are known.
+    pub fn minutes(&self) -> Option<i64> {
+        match (self.started_at, self.ended_at) {
+            (Some(start), Some(end)) => Some((end - start).num_minutes()),
+            _ => None,
+        }
+    }
+
+    /// a copy of the task with its date and times shown in `tz`, or in local time
+    /// if there is none. tasks without timestamps are returned as they are.
+    pub fn localize(&self, tz: Option<Tz>) -> Task {
+        let show = |d: DateTime<FixedOffset>| match tz {
+            Some(tz) => d.with_timezone(&tz).naive_local(),
+            None => d.with_timezone(&chrono::Local).naive_local(),
+        };
+        let mut t = self.clone();
+        if let Some(start) = self.started_at.map(show) {
+            t.date = format!("{}-{}-{}", start.year(), start.month(), start.day());
+            t.time_start = start.format("%H%M").to_string();
+        }
+        if let Some(end) = self.ended_at.map(show) {
+            t.time_end = Some(end.format("%H%M").to_string());
+        }
+        t
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn print(self, style: DurationStyle) -> Option<String> {
+        let name = match self.tags.is_empty() {
+            true => self.task_name,
+            false => format!("{} [{}]", self.task_name, self.tags.join(", ")),
+        };
+        match self.time_end {
+            Some(end) => Some(format!(
+                "{}: Started: {}, ended: {}, Duration: {}",
+                name,
+                self.time_start,
+                end,
+                Duration::from_minutes(self.time_total).format(style)
+            )),
+            _ => Some(format!("{}: Started: {}", name, self.time_start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minutes_across_time_zones() {
+        let mut t = Task::new(
+            "2023-10-20".to_string(),
+            "flight".to_string(),
+            "0900".to_string(),
+            Some("0900".to_string()),
+            0,
+        );
+        assert_eq!(t.minutes(), None);
+
+        // left berlin at 9, landed in new york at 9.
+        t.started_at = DateTime::parse_from_rfc3339("2023-10-20T09:00:00+02:00").ok();
+        t.ended_at = DateTime::parse_from_rfc3339("2023-10-20T09:00:00-04:00").ok();
+        assert_eq!(t.minutes(), Some(360));
+
+        let ny = t.localize(Some(chrono_tz::America::New_York));
+        assert_eq!(ny.time_start, "0300");
+        assert_eq!(ny.time_end, Some("0900".to_string()));
+
+        let json = t.to_json_string();
+        assert!(json.contains(r#""started_at":"2023-10-20T09:00:00+02:00""#));
+        assert_eq!(Task::task_from_string(json).unwrap(), t);
+        assert!(Task::task_from_string("{\"task_name\": 3}".to_string()).is_err());
+    }
+}
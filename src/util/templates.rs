@@ -0,0 +1,92 @@
+use super::tasks::Task;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// A task that is logged often, kept in the `templates` section of the config
+/// under the name used with `timr log`.
+///
+/// # Example
+/// ```json
+/// "templates": { "standup": { "task": "daily standup", "tags": ["meeting"], "minutes": 15 } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Template {
+    /// name of the task logged. defaults to the template's name.
+    pub task: Option<String>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    /// how long the task usually takes.
+    pub minutes: i64,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            task: None,
+            tags: Vec::new(),
+            project: None,
+            minutes: 30,
+        }
+    }
+}
+
+impl Template {
+    /// a task to log from the template called `name`. only the name, tags, and project are set.
+    pub fn task(&self, name: &str) -> Task {
+        let mut t = Task::new(
+            String::new(),
+            self.task.clone().unwrap_or(name.to_string()),
+            String::new(),
+            None,
+            0,
+        );
+        t.tags = self.tags.clone();
+        t.project = self.project.clone();
+        t
+    }
+}
+
+/// A template logged on a schedule by `timr fill`, one of the `recurring` list in the config.
+///
+/// # Example
+/// ```json
+/// "recurring": [{ "template": "standup", "days": ["mon", "tue", "wed", "thu", "fri"], "start": "0930" }]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Recurring {
+    /// name of the template in `templates`.
+    pub template: String,
+    /// the weekdays it happens on.
+    pub days: Vec<Weekday>,
+    /// time it starts (HHMM).
+    pub start: String,
+}
+
+impl Recurring {
+    pub fn happens_on(&self, date: NaiveDate) -> bool {
+        self.days.contains(&date.weekday())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let r: Recurring = serde_json::from_str(
+            r#"{ "template": "sync", "days": ["mon", "Thursday"], "start": "1000" }"#,
+        )
+        .unwrap();
+        assert!(r.happens_on(NaiveDate::from_ymd_opt(2023, 10, 16).unwrap()));
+        assert!(r.happens_on(NaiveDate::from_ymd_opt(2023, 10, 19).unwrap()));
+        assert!(!r.happens_on(NaiveDate::from_ymd_opt(2023, 10, 17).unwrap()));
+
+        let t: Template = serde_json::from_str(r#"{ "tags": ["meeting"] }"#).unwrap();
+        assert_eq!(t.minutes, 30);
+        let task = t.task("sync");
+        assert_eq!(task.task_name, "sync");
+        assert!(task.has_tag("meeting"));
+    }
+}
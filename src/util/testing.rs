@@ -0,0 +1,17 @@
+//! scratch files for tests. only std is used, so that the cli's tests can include it too.
+use std::path::PathBuf;
+
+/// path to a scratch ledger in the temp dir, removed if it already exists.
+pub fn temp_file(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("timr_{}_{}.json", name, std::process::id()));
+    _ = std::fs::remove_file(&path);
+    path.to_string_lossy().to_string()
+}
+
+/// an empty scratch directory in the temp dir, for tests that write more than a ledger.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("timr_{}_{}", name, std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
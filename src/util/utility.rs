@@ -0,0 +1,670 @@
+#![allow(dead_code)]
+use super::{
+    archive, duration,
+    encryption::Lines,
+    events::{self, Event, TaskKey},
+    schema::{self, Header},
+    tasks::Task,
+};
+use anyhow::{Ok, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, ParseResult,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use rand::Rng;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+pub const OUTPUT_FILE: &str = "timr.json";
+
+/// getting our starting and ending time for a task, we calculate the difference
+/// and return a customized string.
+/// ### NOTE
+/// Currently this only works for same day calculations. this does not take dates into consideration.
+///
+/// # Errors
+///
+/// returns an error if either time is not `%H%M`.
+pub fn calc_time_diff(start_time: &str, end_time: &str) -> Result<(String, i64)> {
+    let start = NaiveTime::parse_from_str(start_time, "%H%M")
+        .map_err(|e| anyhow::anyhow!("error parsing {}.  err: {}", start_time, e))?;
+    let end = NaiveTime::parse_from_str(end_time, "%H%M")
+        .map_err(|e| anyhow::anyhow!("error parsing {}.  err: {}", end_time, e))?;
+
+    let total_hours = (end - start).num_hours().abs();
+    let total_min = (end - start).num_minutes() % 60;
+
+    let s: String = match total_hours < 10 {
+        true => match total_min < 10 {
+            true => format!("0{}0{}", total_hours, total_min),
+            false => format!("0{}{}", total_hours, total_min),
+        },
+        false => match total_min < 10 {
+            true => format!("{}0{}", total_hours, total_min),
+            false => format!("{}{}", total_hours, total_min),
+        },
+    };
+
+    Ok((s, (end - start).num_minutes()))
+}
+
+/// adds `minutes` to a `%H%M` formatted time, wrapping around midnight.
+/// # Examples
+/// * `add_minutes("0900", 25)` is "0925"
+pub fn add_minutes(time: &str, minutes: i64) -> Result<String> {
+    let t = NaiveTime::parse_from_str(time, "%H%M")?;
+    Ok((t + Duration::minutes(minutes)).format("%H%M").to_string())
+}
+
+/// parses a time from the ledger or the command line. `%H%M` is what we write,
+/// but older entries dropped the leading zero ("914") and some were written as `%H:%M:%S`.
+pub fn parse_time(time: &str) -> Result<NaiveTime> {
+    let time = time.trim();
+    for fmt in ["%H%M", "%H:%M", "%H:%M:%S"] {
+        if let ParseResult::Ok(t) = NaiveTime::parse_from_str(time, fmt) {
+            return Ok(t);
+        }
+    }
+    if time.len() == 3 && time.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(NaiveTime::parse_from_str(&format!("0{}", time), "%H%M")?);
+    }
+    anyhow::bail!("could not parse time {}", time)
+}
+
+/// how long ago a task that started on `date` at `time` began, in `tz` or in local time if there is none.
+pub fn elapsed_since(date: &str, time: &str, tz: Option<Tz>) -> Result<Duration> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+    Ok(now_in(tz) - resolve_local(date, parse_time(time)?, tz)?)
+}
+
+/// formats a date the way the ledger does, e.g. "2023-10-6".
+pub fn format_date(date: NaiveDate) -> String {
+    format!("{}-{}-{}", date.year(), date.month(), date.day())
+}
+
+/// the current time in `tz`, or in the system's local time if there is none.
+pub fn now_in(tz: Option<Tz>) -> DateTime<FixedOffset> {
+    match tz {
+        Some(tz) => Utc::now().with_timezone(&tz).fixed_offset(),
+        None => chrono::Local::now().fixed_offset(),
+    }
+}
+
+/// the instant the wall clock read `time` on `date` in `tz`, or in local time if there is none.
+///
+/// a time skipped by a DST change is moved forward an hour, and a time that happened twice
+/// is taken the first time.
+pub fn resolve_local(
+    date: NaiveDate,
+    time: NaiveTime,
+    tz: Option<Tz>,
+) -> Result<DateTime<FixedOffset>> {
+    fn pick<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(naive + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|d| d.fixed_offset())
+    }
+    let naive = date.and_time(time);
+    let resolved = match tz {
+        Some(tz) => pick(&tz, naive),
+        None => pick(&chrono::Local, naive),
+    };
+    match resolved {
+        Some(d) => Ok(d),
+        None => anyhow::bail!("{} does not exist in this time zone", naive),
+    }
+}
+
+/// how long ago `task` started. uses its timestamp when there is one,
+/// otherwise its date and time are taken to be in `tz`.
+pub fn task_elapsed(task: &Task, tz: Option<Tz>) -> Result<Duration> {
+    match task.started_at {
+        Some(started) => Ok(Utc::now().signed_duration_since(started)),
+        None => elapsed_since(&task.date, &task.time_start, tz),
+    }
+}
+
+pub fn check_if_task_exists(task: &str, file: &str) -> Result<bool> {
+    let tasks: Vec<Task> = read_incomplete_tasks(file)?;
+    for t in tasks {
+        if t.task_name == task && t.time_end.is_none() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// adds `t` to the ledger by appending a start event, without rewriting the file.
+pub fn output_task_to_file(t: Task, file: &str) -> Result<()> {
+    events::append(&[Event::Start { task: t }], file)
+}
+
+pub fn generate_sample_task() -> Task {
+    let date = chrono::Local::now();
+    let date_s = format!("{}-{}-{}", date.year(), date.month(), date.day());
+
+    let mut rng = rand::thread_rng();
+
+    // generate starting time (between 5-am and 9am)
+    let time_start =
+        NaiveTime::from_hms_opt(rng.gen_range(5..12), rng.gen_range(0..59), 0).unwrap();
+
+    // generate ending time (between 2pm and 7pm)
+    let time_end = NaiveTime::from_hms_opt(rng.gen_range(13..18), rng.gen_range(0..59), 0).unwrap();
+
+    let tasks = [
+        "sleeping",
+        "refactoring",
+        "writing software",
+        "fixing bugs",
+        "creating new feature",
+        "debugging",
+        "stuck, pls help",
+        "getting coffee",
+    ];
+
+    let random_task: String = tasks
+        .get(rng.gen_range(0..tasks.len()))
+        .unwrap()
+        .to_string();
+
+    Task::new(
+        date_s,
+        random_task,
+        time_start.format("%H%M").to_string(),
+        Some(time_end.format("%H%M").to_string()),
+        duration::Duration::from(time_end - time_start).minutes(),
+    )
+}
+
+/// I am tired of having to remember how to get a formatted version of the current time,
+/// so now we have this function.
+/// # Return
+/// * string is formatted into the `%H%M`, seconds are not included.
+/// # Examples
+/// * "0645"
+pub fn get_time() -> Result<String> {
+    Ok(chrono::Local::now().time().format("%H%M").to_string())
+}
+
+pub fn get_date() -> Result<String> {
+    let date = chrono::Local::now();
+    Ok(format!("{}-{}-{}", date.year(), date.month(), date.day()))
+}
+
+/// get_task takes a task name and looks for the most recent task by that name.
+///
+///  [`has_ended`] parameter: if `true`, finds most recent task by name. if `false`,
+///  finds most recent task matching the name that <b>does not</b> have a end time.
+///
+/// # Returns
+/// returns an [`option<task>`] if found, `None` otherwise, or an error if the ledger
+/// cannot be read.
+pub fn get_task(task_name: &str, file: Option<&str>, has_ended: bool) -> Result<Option<Task>> {
+    let task: Vec<Task> = get_tasks_by_name(task_name.to_string(), file.unwrap_or(OUTPUT_FILE))?;
+    Ok(match has_ended {
+        true => task.into_iter().find(|t| t.task_name == task_name),
+        false => task.into_iter().find(|t| t.time_end.is_none()),
+    })
+}
+
+pub fn get_tasks_by_name(task_name: String, filename: &str) -> Result<Vec<Task>> {
+    let tasks = read_all_tasks(filename)?;
+    let mut collection: Vec<Task> = Vec::new();
+
+    for t in tasks {
+        if t.task_name == task_name {
+            collection.push(t);
+        }
+    }
+
+    Ok(collection)
+}
+
+pub fn read_all_tasks(filename: &str) -> Result<Vec<Task>> {
+    // a ledger that has not been written to yet has no tasks.
+    if !Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+
+    events::replay(&std::fs::read_to_string(filename)?, filename)
+}
+
+pub fn read_incomplete_tasks(file: &str) -> Result<Vec<Task>> {
+    let mut rtn: Vec<Task> = Vec::new();
+
+    // reading all the tasks from the file will get problematic, so this is temporary.
+    let raw: Vec<Task> = read_all_tasks(file)?;
+    for s in raw.into_iter() {
+        if s.time_end.is_none() {
+            rtn.push(s);
+        }
+    }
+    Ok(rtn)
+}
+
+/// reads the tasks started in the `days` days before `today`, today included.
+pub fn read_tasks_from_day_range(days: i32, today: NaiveDate, file: &str) -> Result<Vec<Task>> {
+    read_tasks_between(Some(today - Duration::days(days as i64)), today, file)
+}
+
+/// reads the tasks started between `from` and `to`, both days included, from the ledger
+/// and the archive segments covering those days. with no `from`, every task up to `to` is returned.
+pub fn read_tasks_between(from: Option<NaiveDate>, to: NaiveDate, file: &str) -> Result<Vec<Task>> {
+    let mut tasks: Vec<Task> = read_all_tasks(file)?
+        .into_iter()
+        .filter(|t| match t.naive_date() {
+            Some(d) => from.is_none_or(|f| d >= f) && d <= to,
+            None => false,
+        })
+        .collect();
+    tasks.extend(archive::read_archived(from, to, file)?);
+    Ok(tasks)
+}
+
+/// the first day of the week `date` is in, for weeks starting on `start`.
+pub fn week_start(date: NaiveDate, start: Weekday) -> NaiveDate {
+    let offset = (7 + date.weekday().num_days_from_monday() - start.num_days_from_monday()) % 7;
+    date - Duration::days(offset as i64)
+}
+
+/// the first day of ISO week `week` of the ISO week-year `year`, for weeks starting on `start`.
+///
+/// ISO weeks start on monday. for weeks starting on another day, the week is the one
+/// that contains the ISO week's monday.
+pub fn iso_week_start(year: i32, week: u32, start: Weekday) -> Option<NaiveDate> {
+    let monday = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)?;
+    Some(week_start(monday, start))
+}
+
+/// reads the tasks from the week beginning on `start`.
+pub fn read_tasks_in_week(start: NaiveDate, file: &str) -> Result<Vec<Task>> {
+    read_tasks_between(Some(start), start + Duration::days(6), file)
+}
+
+/// reads this week's tasks, in local time, for weeks starting on `start`.
+pub fn read_tasks_this_week(file: &str, start: Weekday) -> Result<Vec<Task>> {
+    let today = chrono::Local::now().date_naive();
+    read_tasks_in_week(week_start(today, start), file)
+}
+
+pub fn sum_task_total_time(t1: Task, t2: Task) -> i64 {
+    if t1.task_name != t2.task_name {
+        return -1;
+    }
+    t1.time_total + t2.time_total
+}
+
+/// Updates the task in the json file with the same task name, date, and start time.
+/// the task is written as it is, total included. the change is appended as an event,
+/// so the file is not rewritten.
+///
+/// # Errors
+///
+/// This function will return an error if the ledger cannot be written.
+pub fn update_task_in_file(task: Task, file: &str) -> Result<()> {
+    let event = match task.time_end.is_some() {
+        true => Event::End { task },
+        false => Event::Amend {
+            old: TaskKey::of(&task),
+            task,
+        },
+    };
+    events::append(&[event], file)
+}
+
+/// Replaces `old` with `new` in the json file, matching on its id, or its task name, date, and start time.
+/// an archived task is replaced in the archive segment it is in.
+///
+/// Unlike [`update_task_in_file`], the replacement is written as-is, so it can be used
+/// when the start time itself is being changed.
+///
+/// # Errors
+///
+/// This function will return an error if `old` is not in the file or its archive.
+pub fn replace_task_in_file(old: &Task, new: Task, file: &str) -> Result<()> {
+    let old = TaskKey::of(old);
+    if read_all_tasks(file)?.iter().any(|t| old.matches(t)) {
+        return events::append(&[Event::Amend { old, task: new }], file);
+    }
+    if !archive::replace_archived(&old, Some(new), file)? {
+        anyhow::bail!("could not find task {} in {}", old.task_name, file);
+    }
+    Ok(())
+}
+
+/// Deletes `task` from the json file, matching on its id, or its task name, date, and start time.
+/// an archived task is deleted from the archive segment it is in.
+///
+/// # Errors
+///
+/// This function will return an error if `task` is not in the file or its archive.
+pub fn delete_task_in_file(task: &Task, file: &str) -> Result<()> {
+    let old = TaskKey::of(task);
+    if read_all_tasks(file)?.iter().any(|t| old.matches(t)) {
+        return events::append(&[Event::Delete { old }], file);
+    }
+    if !archive::replace_archived(&old, None, file)? {
+        anyhow::bail!("could not find task {} in {}", old.task_name, file);
+    }
+    Ok(())
+}
+
+/// Overwrites the json file with `collection`, one task per line, as a snapshot with no events.
+/// the file is written in the current version, and stays encrypted if it was.
+pub fn write_all_tasks(collection: &[Task], file: &str) -> Result<()> {
+    let encryption = schema::header_of(file)?.and_then(|h| h.encryption);
+    let header = Header {
+        encryption,
+        ..Header::current()
+    };
+    write_ledger(collection, &header, file)
+}
+
+/// Overwrites the json file with `header` followed by `collection`, one task per line.
+/// the lines are sealed if the header says the file is encrypted.
+pub fn write_ledger(collection: &[Task], header: &Header, file: &str) -> Result<()> {
+    let lines = Lines::of(header, file)?;
+    // buf will store our collection, after being converted to bytes.
+    let mut buf = header.to_json_string();
+
+    for s in collection {
+        buf += &lines.seal(&serde_json::to_string(s)?);
+        buf.push('\n');
+    }
+
+    let mut f = File::create(file)?;
+    Ok(f.write_all(buf.as_bytes())?)
+}
+
+/// Replays the events in the json file into a new snapshot, so that reading it is quicker.
+/// returns the number of tasks in the snapshot.
+pub fn compact(file: &str) -> Result<usize> {
+    let tasks = read_all_tasks(file)?;
+    if Path::new(file).exists() {
+        write_all_tasks(&tasks, file)?;
+    }
+    Ok(tasks.len())
+}
+
+/// Compares the converted `NativeDate` date from two Tasks,
+/// and get the absolute difference of days between the two.
+///
+/// # Errors
+///
+/// returns an error if either task is missing a valid date.
+/// # Example
+/// ```
+/// # use timr::{util::utility::compare_dates, Task};
+/// let t1: Task = Task { date: "2023-9-1".to_string(), ..Default::default() };
+/// let t2: Task = Task { date: "2023-9-7".to_string(), ..Default::default() };
+/// assert_eq!(compare_dates(&t2, &t1)?, 6);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn compare_dates(t1: &Task, t2: &Task) -> Result<i32> {
+    match (t1.naive_date(), t2.naive_date()) {
+        (Some(d1), Some(d2)) => Ok(i64::abs((d1 - d2).num_days()) as i32),
+        _ => anyhow::bail!(
+            "{} and {} need valid dates to compare",
+            t1.task_name,
+            t2.task_name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    // required imports for testing
+    use super::*;
+    use crate::util::testing::temp_file;
+    use crate::util::utility;
+    // ----------------------------
+
+    #[test]
+    pub fn test_calc_time_diff() {
+        let start = "0700";
+        let end = "1200";
+        let res = calc_time_diff(start, end).unwrap().0;
+        assert_eq!(res, "0500".to_string());
+
+        let start = "0700";
+        let end = "1900";
+        let res = calc_time_diff(start, end).unwrap().0;
+        assert_eq!(res, "1200".to_string());
+
+        let start = "2300";
+        let end = "0100";
+        let res = calc_time_diff(start, end).unwrap().0;
+        // ! this should be 6 hours, but since date is not a factor we get 18.
+        assert_eq!(res, "2200".to_string());
+    }
+
+    #[test]
+    pub fn test_add_minutes() {
+        assert_eq!(add_minutes("0900", 25).unwrap(), "0925");
+        assert_eq!(add_minutes("2350", 20).unwrap(), "0010");
+        assert!(add_minutes("nope", 5).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_time() {
+        let t = NaiveTime::from_hms_opt(9, 14, 0).unwrap();
+        assert_eq!(parse_time("0914").unwrap(), t);
+        assert_eq!(parse_time("914").unwrap(), t);
+        assert_eq!(parse_time("09:14").unwrap(), t);
+        assert_eq!(parse_time("09:14:00").unwrap(), t);
+        assert!(parse_time("9a4").is_err());
+    }
+
+    #[test]
+    pub fn test_elapsed_since() {
+        let elapsed = elapsed_since("2023-10-6", "1900", None).unwrap();
+        assert!(elapsed > Duration::days(365));
+    }
+
+    #[test]
+    pub fn test_week_start() {
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // a wednesday
+        let wed = date("2023-10-18");
+        assert_eq!(week_start(wed, Weekday::Mon), date("2023-10-16"));
+        assert_eq!(week_start(wed, Weekday::Sun), date("2023-10-15"));
+        assert_eq!(
+            week_start(date("2023-10-15"), Weekday::Sun),
+            date("2023-10-15")
+        );
+        assert_eq!(
+            week_start(date("2023-10-15"), Weekday::Mon),
+            date("2023-10-09")
+        );
+
+        // new year's day 2021 is in ISO week 53 of 2020.
+        assert_eq!(
+            iso_week_start(2020, 53, Weekday::Mon),
+            Some(date("2020-12-28"))
+        );
+        assert_eq!(
+            iso_week_start(2024, 1, Weekday::Sun),
+            Some(date("2023-12-31"))
+        );
+        assert_eq!(iso_week_start(2023, 53, Weekday::Mon), None);
+    }
+
+    #[test]
+    pub fn test_resolve_local() {
+        let tz: Option<Tz> = Some("America/New_York".parse().unwrap());
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let time = |s| parse_time(s).unwrap();
+
+        // 0130 happens twice when DST ends, the first one is in EDT.
+        let d = resolve_local(date("2023-11-05"), time("0130"), tz).unwrap();
+        assert_eq!(d.to_rfc3339(), "2023-11-05T01:30:00-04:00");
+
+        // 0230 never happens when DST starts.
+        let d = resolve_local(date("2023-03-12"), time("0230"), tz).unwrap();
+        assert_eq!(d.to_rfc3339(), "2023-03-12T03:30:00-04:00");
+
+        assert_eq!(format_date(date("2023-10-06")), "2023-10-6");
+    }
+
+    #[test]
+    pub fn test_get_task() {
+        let file = temp_file("get_task");
+        let t: Task = Task::new(
+            get_date().unwrap(),
+            "debugging".to_string(),
+            "1230".to_string(),
+            Some("1330".to_string()),
+            60,
+        );
+        _ = output_task_to_file(t.clone(), &file);
+
+        let result = get_task("debugging", Some(&file), true).unwrap().unwrap();
+        assert_eq!(t, result);
+    }
+
+    #[test]
+    pub fn test_replace_task_in_file() {
+        let file = temp_file("replace_task");
+        let t: Task = Task::new(
+            get_date().unwrap(),
+            "debugging".to_string(),
+            "1230".to_string(),
+            None,
+            0,
+        );
+        output_task_to_file(t.clone(), &file).unwrap();
+
+        let mut amended = t.clone();
+        amended.time_start = "1200".to_string();
+        replace_task_in_file(&t, amended.clone(), &file).unwrap();
+
+        assert_eq!(read_all_tasks(&file).unwrap(), vec![amended.clone()]);
+        assert!(replace_task_in_file(&t, t.clone(), &file).is_err());
+
+        // the change was appended, and compacting leaves only the snapshot.
+        assert_eq!(std::fs::read_to_string(&file).unwrap().lines().count(), 3);
+        assert_eq!(compact(&file).unwrap(), 1);
+        let snapshot = Header::current().to_json_string() + &amended.to_json_string();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), snapshot);
+    }
+
+    // #[test]
+    // pub fn test_get_task() {
+    //     let t: Task = Task::new(
+    //         get_date().unwrap(),
+    //         "debugging".to_string(),
+    //         "1230".to_string(),
+    //         Some("1330".to_string()),
+    //         60,
+    //     );
+    //     _ = output_task_to_file(t.clone());
+
+    //     // little delay
+    //     let delay = time::Duration::from_millis(2000);
+    //     thread::sleep(delay);
+
+    //     let result = get_task("debugging", Some(OUTPUT_FILE), true).unwrap();
+
+    //     assert_eq!(t, result);
+    // }
+
+    // #[test]
+    // pub fn test_output_task_to_file() {
+    //     let t = generate_sample_task();
+    //     let t_json = serde_json::to_string(&t).unwrap();
+
+    //     _ = output_task_to_file(t);
+
+    //     // little delay
+    //     let delay = time::Duration::from_millis(2000);
+    //     thread::sleep(delay);
+
+    //     let f = read_all_tasks("timr.json").unwrap();
+
+    //     let task_to_json = serde_json::to_string(&f[0]).unwrap();
+
+    //     // dbg!(&task_to_json, &t_json);
+
+    //     assert_eq!(t_json, task_to_json);
+    // }
+
+    #[test]
+    fn test_compare_dates() {
+        let t1: Task = Task::new(
+            "2023-9-1".to_string(),
+            "debugging".to_string(),
+            "11:07:32".to_string(),
+            Some("16:00:53".to_string()),
+            293,
+        );
+        let t2: Task = Task::new(
+            "2023-9-7".to_string(),
+            "debugging".to_string(),
+            "11:07:32".to_string(),
+            Some("16:00:53".to_string()),
+            293,
+        );
+
+        let comparison = compare_dates(&t2, &t1).unwrap();
+        assert_eq!(comparison, 6);
+        assert!(compare_dates(&t2, &Task::default()).is_err());
+    }
+
+    #[test]
+    pub fn test_sum_task_total_time() {
+        let task1 = Task::new(
+            "2023-9-7".to_string(),
+            "test".to_string(),
+            "0800".to_string(),
+            Some("1200".to_string()),
+            240,
+        );
+        let task2 = Task::new(
+            "2023-9-8".to_string(),
+            "test".to_string(),
+            "0800".to_string(),
+            Some("1200".to_string()),
+            240,
+        );
+
+        let result = sum_task_total_time(task1, task2);
+        assert_eq!(result, 480);
+
+        let t1: Task = Task::new(
+            String::from("date"),
+            String::from("task1"),
+            String::from("0600"),
+            Some(String::from("0800")),
+            120,
+        );
+
+        let t2: Task = Task::new(
+            String::from("date"),
+            String::from("task1"),
+            String::from("0700"),
+            Some(String::from("1000")),
+            180,
+        );
+
+        assert_eq!(300, utility::sum_task_total_time(t1, t2));
+    }
+
+    #[test]
+    pub fn test_serde_json() {
+        let t: Task = generate_sample_task();
+        let file = temp_file("serde_json");
+        // ledgers written before the event log are a snapshot with windows line endings.
+        let json_str = format!("{}\r\n", serde_json::to_string(&t).unwrap());
+        std::fs::write(&file, json_str).unwrap();
+
+        assert_eq!(read_all_tasks(&file).unwrap(), vec![t]);
+    }
+}
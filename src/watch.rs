@@ -0,0 +1,246 @@
+use anyhow::{bail, Result};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use std::io::{Stdout, Write};
+use std::time::Duration;
+use timr::{
+    util::{duration, utility::*},
+    Task, Timr,
+};
+
+/// state of the watch screen between refreshes.
+#[derive(Default)]
+struct Watch {
+    /// index into the running tasks followed by the paused ones.
+    selected: usize,
+    /// tasks paused from this screen, to be started again with `p`.
+    paused: Vec<Task>,
+    /// name being typed for `s`witch, if any.
+    input: Option<String>,
+    /// result of the last action.
+    message: String,
+}
+
+/// formats a duration as `h:mm:ss`.
+fn hms(d: chrono::Duration) -> String {
+    let secs = d.num_seconds().max(0);
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// runs the watch screen until the user quits.
+pub fn run(timr: &Timr) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = watch_loop(timr, &mut stdout);
+
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn watch_loop(timr: &Timr, stdout: &mut Stdout) -> Result<()> {
+    let mut w = Watch::default();
+    loop {
+        let status = timr.status()?;
+        let running: Vec<Task> = status.running.iter().map(|(t, _)| t.clone()).collect();
+        let rows = running.len() + w.paused.len();
+        w.selected = w.selected.min(rows.saturating_sub(1));
+
+        draw(stdout, timr, &w, &running, status.today_total)?;
+
+        // redraw every second even if nothing is pressed.
+        if !event::poll(Duration::from_secs(1))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(k) if k.kind == KeyEventKind::Press => k.code,
+            _ => continue,
+        };
+
+        match w.act(timr, &running, key) {
+            Ok(true) => return Ok(()),
+            Ok(false) => (),
+            // an action that fails is reported, and the screen stays up.
+            Err(e) => w.message = e.to_string(),
+        }
+    }
+}
+
+impl Watch {
+    /// does what `key` asks for. returns `true` once the user quits.
+    fn act(&mut self, timr: &Timr, running: &[Task], key: KeyCode) -> Result<bool> {
+        let rows = running.len() + self.paused.len();
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => _ = input.pop(),
+                KeyCode::Esc => self.input = None,
+                KeyCode::Enter => {
+                    let name = self.input.take().unwrap_or_default();
+                    if !name.trim().is_empty() {
+                        // with a paused task selected, switching would end every running one.
+                        let from = match running.get(self.selected) {
+                            Some(t) => Some(t.task_name.as_str()),
+                            None if running.is_empty() => None,
+                            None => bail!("select a running task to switch from"),
+                        };
+                        let (_, t) = timr.switch(name.trim(), from, None)?;
+                        self.message = format!("switched to {}", t.task_name);
+                    }
+                }
+                _ => (),
+            }
+            return Ok(false);
+        }
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(rows.saturating_sub(1))
+            }
+            KeyCode::Char('e') => {
+                if let Some(t) = running.get(self.selected) {
+                    let t = timr.localize(&timr.stop(&t.task_name, None)?);
+                    self.message = format!("{} ended at: {}", t.task_name, t.time_end.unwrap());
+                }
+            }
+            KeyCode::Char('p') => match running.get(self.selected) {
+                Some(t) => {
+                    let t = timr.stop(&t.task_name, None)?;
+                    self.message = format!("{} paused", t.task_name);
+                    self.paused.push(t);
+                }
+                None => {
+                    if let Some(i) = self.selected.checked_sub(running.len()) {
+                        if i < self.paused.len() {
+                            // a copy keeps its tags and project.
+                            let t = timr.start_task(self.paused[i].clone(), None)?;
+                            self.paused.remove(i);
+                            self.message = format!("{} resumed", t.task_name);
+                        }
+                    }
+                }
+            },
+            KeyCode::Char('s') => self.input = Some(String::new()),
+            _ => (),
+        }
+        Ok(false)
+    }
+}
+
+fn draw(
+    stdout: &mut Stdout,
+    timr: &Timr,
+    w: &Watch,
+    running: &[Task],
+    today_total: i64,
+) -> Result<()> {
+    queue!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        SetAttribute(Attribute::Bold),
+        Print(format!(
+            "timr watch  {}",
+            timr.now().format("%Y-%m-%d %H:%M:%S")
+        )),
+        SetAttribute(Attribute::Reset),
+    )?;
+
+    let mut lines: Vec<String> = vec![String::new()];
+    if running.is_empty() && w.paused.is_empty() {
+        lines.push("  no running tasks.".to_string());
+    }
+    for (i, t) in running.iter().enumerate() {
+        let elapsed = task_elapsed(t, timr.config().timezone)?;
+        lines.push(format!(
+            "{} {:<30} started {} {:>10}",
+            if i == w.selected { ">" } else { " " },
+            t.task_name,
+            timr.localize(t).time_start,
+            hms(elapsed)
+        ));
+    }
+    for (i, t) in w.paused.iter().enumerate() {
+        lines.push(format!(
+            "{} {:<30} paused  {}",
+            if running.len() + i == w.selected {
+                ">"
+            } else {
+                " "
+            },
+            t.task_name,
+            timr.localize(t).time_end.unwrap_or_default()
+        ));
+    }
+    lines.push(String::new());
+    let style = timr.config().duration_style;
+    lines.push(format!(
+        "today: {}",
+        duration::Duration::from_minutes(today_total).format(style)
+    ));
+    lines.push(String::new());
+    match &w.input {
+        Some(input) => lines.push(format!("switch to: {}_", input)),
+        None => lines.push("[e]nd  [p]ause/resume  [s]witch  [q]uit".to_string()),
+    }
+    lines.push(w.message.clone());
+
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16 + 1), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_file;
+
+    #[test]
+    fn test_pause_and_switch() {
+        let timr = Timr::new(temp_file("watch_pause"));
+        let mut t = Task::new_task_today();
+        t.task_name = "writing".to_string();
+        t.project = Some("docs".to_string());
+        timr.start_task(t, Some("0000")).unwrap();
+        timr.start("review", Some("0000")).unwrap();
+        let running = |timr: &Timr| -> Vec<Task> {
+            timr.status()
+                .unwrap()
+                .running
+                .into_iter()
+                .map(|(t, _)| t)
+                .collect()
+        };
+
+        // pause "writing", and select it among the paused tasks.
+        let mut w = Watch {
+            selected: 1,
+            ..Default::default()
+        };
+        w.act(&timr, &running(&timr), KeyCode::Char('p')).unwrap();
+        assert_eq!(w.paused[0].task_name, "writing");
+
+        // switching from a paused task is refused, and leaves "review" running.
+        w.input = Some("email".to_string());
+        assert!(w.act(&timr, &running(&timr), KeyCode::Enter).is_err());
+        assert!(timr.is_running("review").unwrap());
+
+        // resuming keeps the project.
+        w.act(&timr, &running(&timr), KeyCode::Char('p')).unwrap();
+        let resumed = timr.list(timr::ListFilter::Running).unwrap();
+        let writing = resumed.iter().find(|t| t.task_name == "writing").unwrap();
+        assert_eq!(writing.project.as_deref(), Some("docs"));
+        assert!(w.paused.is_empty());
+    }
+}